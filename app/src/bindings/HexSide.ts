@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * All of the sides of a hexagonal tile
+ */
+export type HexSide = "nw" | "ne" | "w" | "e" | "sw" | "se";
@@ -0,0 +1,58 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Harbour } from "./Harbour";
+import type { HarbourPlacement } from "./HarbourPlacement";
+import type { TileID } from "./TileID";
+import type { TileMap } from "./TileMap";
+import type { TileTerrain } from "./TileTerrain";
+
+/**
+ * The configuration of any given map stored usually as as json file.
+ *
+ * Serializing the config and deserializing it back always produces the same
+ * config. The canonical JSON form, as produced by [`MapConfig::to_json`],
+ * lists every field (even the defaulted ones) in the declaration order.
+ */
+export type MapConfig = { 
+/**
+ * The amount of different terrains in use in specified map
+ */
+tileBank: TileMap<number>, mapSize: [number, number], 
+/**
+ * Positions of all of the tiles. Index signifies TileID,
+ * while value, is the coordinated in a squared-off map
+ */
+tilePlacement: Array<[number, number]>, 
+/**
+ * If randomization is turned off, how will the distribution
+ * of terrains lay itself.
+ */
+defaultTiles: Array<TileTerrain>, 
+/**
+ * Terrains which should always be associated with specified TileIDs
+ * and not randomized if randomization is requested
+ */
+fixedTiles: TileMap<Array<TileID>>, 
+/**
+ * The positions of the harbours and their rotation within specified
+ * tile. The index signifies HarborID, while the value contains the
+ * coordinate within which the harbour is places as well a the side
+ * to which it is attached within that tile.
+ */
+harbourPlacement: Array<HarbourPlacement>, 
+/**
+ * If randomization is turned off, how will the distribution
+ * of harbours lay itself.
+ */
+defaultHarbours: Array<Harbour>, 
+/**
+ * Dice markers laid on top of each of the tiles, if randomization is
+ * turned off. Index signifies TileID, and deserts have no marker.
+ * If none are specified, the markers of the base game are laid out
+ * in the order of TileIDs instead.
+ */
+defaultDiceMarkers: Array<number | null>, 
+/**
+ * The desert the robber starts the game on, if the rules place it on
+ * a desert. If not specified, the robber starts on the first desert.
+ */
+robberTile: TileID | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TileMap<T> = { field: T, pasture: T, forest: T, mesa: T, mountains: T, desert: T, };
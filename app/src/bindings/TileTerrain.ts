@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The six tile terrains in the game of Catan
+ */
+export type TileTerrain = "field" | "pasture" | "forest" | "mesa" | "mountains" | "desert";
@@ -0,0 +1,137 @@
+use catan::{
+    decode_config,
+    ids::{PlayerID, SettlePlaceID},
+    network::{BuildableCache, OccupancyIndex},
+    rules::GameRules,
+    topology::{verify_topology, BoardTopology},
+    types::{SettlePlace, TileTerrain},
+    GameState, MapConfig, MapConfigBuilder,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+/// Rectangular island of 12x12 tiles, way bigger than any of the official maps
+fn large_map() -> MapConfig {
+    let terrains = TileTerrain::ALL;
+    let mut builder = MapConfigBuilder::new();
+    for y in 0..12 {
+        for x in 0..12 {
+            builder = builder.add_tile([x, y], terrains[(x + y) as usize % terrains.len()]);
+        }
+    }
+    builder.build().unwrap()
+}
+
+/// Game of four players, each with a settlement and a road of up to 12
+/// pieces walked from it. The settlements are spread over the board.
+fn mid_game(config: MapConfig) -> GameState {
+    let mut state = decode_config(config, 4).unwrap();
+    let settle_places = state.board().settle_places().len();
+    for player in 0..4 {
+        let player = PlayerID(player);
+        let start = settle_places * usize::from(player) / 4;
+        let mut at = SettlePlaceID::try_from(start).unwrap();
+        state.settle_place.occupancy[at] = SettlePlace::Settlement(player);
+        state.player.settlements[player].push(at);
+        for _ in 0..12 {
+            let board = state.board();
+            let Some(road) = board
+                .roads_of(at)
+                .iter()
+                .copied()
+                .find(|road| board.owner_of(*road).is_none())
+            else {
+                break;
+            };
+            let [a, b] = board.ends_of(road);
+            at = if a == at { b } else { a };
+            state.road.owner[road] = Some(player);
+            state.player.placed_roads[player].push(road);
+        }
+    }
+    state
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_config");
+    for (name, config) in [("standard", MapConfig::standard()), ("large", large_map())] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || config.clone(),
+                |config| decode_config(config, 4).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Starting the game on the shared topology, compared to `decode_config`
+fn instantiate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("instantiate");
+    let rules = GameRules::default();
+    for (name, config) in [("standard", MapConfig::standard()), ("large", large_map())] {
+        let topology = BoardTopology::new(config).unwrap();
+        group.bench_function(name, |b| b.iter(|| black_box(&topology).instantiate(4, &rules)));
+    }
+    group.bench_function("standard_static", |b| {
+        b.iter(|| BoardTopology::standard().instantiate(4, &rules))
+    });
+    group.finish();
+}
+
+fn topology(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_topology");
+    for (name, config) in [("standard", MapConfig::standard()), ("large", large_map())] {
+        let state = decode_config(config, 4).unwrap();
+        group.bench_function(name, |b| b.iter(|| verify_topology(black_box(&state))));
+    }
+    group.finish();
+}
+
+fn longest_road(c: &mut Criterion) {
+    let mut group = c.benchmark_group("longest_road");
+    for (name, config) in [("standard", MapConfig::standard()), ("large", large_map())] {
+        let state = mid_game(config);
+        group.bench_function(name, |b| b.iter(|| black_box(&state).longest_road(PlayerID(0))));
+    }
+    group.finish();
+}
+
+/// Buildable spots computed from the relations, from the occupancy index,
+/// and served by the cache
+fn buildable(c: &mut Criterion) {
+    let mut group = c.benchmark_group("buildable");
+    for (name, config) in [("standard", MapConfig::standard()), ("large", large_map())] {
+        let state = mid_game(config);
+        let player = PlayerID(0);
+        group.bench_function(format!("settle_places/{name}"), |b| {
+            b.iter(|| black_box(&state).buildable_settle_places(player))
+        });
+        group.bench_function(format!("roads/{name}"), |b| {
+            b.iter(|| black_box(&state).buildable_roads(player))
+        });
+        let index = OccupancyIndex::new(&state);
+        group.bench_function(format!("indexed_settle_places/{name}"), |b| {
+            b.iter(|| black_box(&index).buildable_settle_places(&state, player))
+        });
+        let mut cache = BuildableCache::new();
+        group.bench_function(format!("cached/{name}"), |b| {
+            b.iter(|| {
+                cache.settle_places(&state, player).map(<[_]>::len);
+                cache.roads(&state, player).map(<[_]>::len)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn clone(c: &mut Criterion) {
+    let state = decode_config(MapConfig::standard(), 4).unwrap();
+    let mut rollout = state.clone();
+    c.bench_function("clone_from/standard", |b| {
+        b.iter(|| rollout.clone_from(black_box(&state)))
+    });
+}
+
+criterion_group!(benches, decode, instantiate, topology, longest_road, buildable, clone);
+criterion_main!(benches);
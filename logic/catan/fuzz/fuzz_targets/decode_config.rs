@@ -0,0 +1,19 @@
+#![no_main]
+
+//! Feed arbitrary JSON into the config deserialization and decoding. Neither
+//! is allowed to panic: malformed configs should always produce an error.
+//!
+//! Run with `cargo +nightly fuzz run decode_config` from `logic/catan`.
+
+use catan::{decode_config, MapConfig};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&player_count, json)) = data.split_first() else {
+        return;
+    };
+    let Ok(config) = serde_json::from_slice::<MapConfig>(json) else {
+        return;
+    };
+    let _ = decode_config(config, player_count % 6);
+});
@@ -7,10 +7,34 @@ use std::ops::{Index, IndexMut};
 /// Vec are the keys representing association of K -> V.
 /// 
 /// Keys must be convertible from and to usize.
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Keys are never stored, so the list is Send and Sync whenever the values are,
+/// regardless of the key type.
+#[derive(PartialEq, Eq, Hash)]
 pub struct AdjacencyList<K, V> {
     values: Vec<V>,
-    _phantom: PhantomData<K>,
+    _phantom: PhantomData<fn() -> K>,
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<AdjacencyList<*const (), u8>>();
+    assert_send_sync::<Iter<'static, *const (), u8>>();
+};
+
+impl<K, V: Clone> Clone for AdjacencyList<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Reuses the allocation of the list (and of the values, if they
+    /// are themselves allocated), instead of allocating a new one.
+    fn clone_from(&mut self, source: &Self) {
+        self.values.clone_from(&source.values);
+    }
 }
 
 impl<K, V> std::fmt::Debug for AdjacencyList<K, V>
@@ -47,6 +71,44 @@ impl<K, V> AdjacencyList<K, V> {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// The amount of keys (and values) stored in the list
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterate over all of the values, in the order of their keys
+    pub fn values(&self) -> std::slice::Iter<'_, V> {
+        self.values.iter()
+    }
+
+    /// Iterate mutably over all of the values, in the order of their keys
+    pub fn values_mut(&mut self) -> std::slice::IterMut<'_, V> {
+        self.values.iter_mut()
+    }
+}
+
+impl<K, V> AdjacencyList<K, V>
+where
+    K: Into<usize>,
+{
+    /// Get the value associated with the key, or None if there is no such key
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.values.get(key.into())
+    }
+
+    /// Get the value associated with the key, or None if there is no such key
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.values.get_mut(key.into())
+    }
+
+    pub fn contains_key(&self, key: K) -> bool {
+        key.into() < self.values.len()
+    }
 }
 
 impl<K, V> AdjacencyList<K, V>
@@ -60,6 +122,95 @@ where
         self.values.push(value);
         id
     }
+
+    /// Shorten the list, dropping every mapping with the key >= len
+    pub fn truncate(&mut self, len: usize) {
+        self.values.truncate(len);
+    }
+
+    /// Iterate over all of the key-value pairs, in the order of keys
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.into_iter()
+    }
+
+    /// Iterate over all of the key-value pairs, with the mutable access
+    /// to the values, in the order of keys
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.into_iter()
+    }
+
+    /// Iterate over all of the keys present in the list
+    pub fn keys(&self) -> Keys<K> {
+        Keys {
+            range: 0..self.values.len(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Walk two relations of the same entity in lockstep, yielding the values
+    /// both of them have for each key. Both relations are expected to have
+    /// the same amount of keys, which is checked in debug builds.
+    pub fn zip<'a, B>(
+        &'a self,
+        other: &'a AdjacencyList<K, B>,
+    ) -> impl ExactSizeIterator<Item = (K, &'a V, &'a B)> + 'a {
+        debug_assert_eq!(self.len(), other.len(), "zipped relations differ in length");
+        self.iter()
+            .zip(other.values())
+            .map(|((key, a), b)| (key, a, b))
+    }
+
+    /// Remove all of the mappings from the list, yielding them by value
+    pub fn drain(&mut self) -> IntoIter<K, V> {
+        IntoIter {
+            values: std::mem::take(&mut self.values).into_iter().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The same as [`AdjacencyList::zip`], but for three relations at once
+    pub fn zip3<'a, B, C>(
+        &'a self,
+        b: &'a AdjacencyList<K, B>,
+        c: &'a AdjacencyList<K, C>,
+    ) -> impl ExactSizeIterator<Item = (K, &'a V, &'a B, &'a C)> + 'a {
+        debug_assert_eq!(self.len(), c.len(), "zipped relations differ in length");
+        self.zip(b)
+            .zip(c.values())
+            .map(|((key, a, b), c)| (key, a, b, c))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, V> AdjacencyList<K, V>
+where
+    K: TryFrom<usize> + Send,
+    K::Error: std::fmt::Debug,
+{
+    /// Iterate over all of the key-value pairs in parallel
+    pub fn par_iter(&self) -> impl rayon::iter::IndexedParallelIterator<Item = (K, &V)>
+    where
+        V: Sync,
+    {
+        use rayon::prelude::*;
+        self.values
+            .par_iter()
+            .enumerate()
+            .map(|(idx, value)| (idx.try_into().unwrap(), value))
+    }
+
+    /// Iterate over all of the key-value pairs in parallel, with the
+    /// mutable access to the values
+    pub fn par_iter_mut(&mut self) -> impl rayon::iter::IndexedParallelIterator<Item = (K, &mut V)>
+    where
+        V: Send,
+    {
+        use rayon::prelude::*;
+        self.values
+            .par_iter_mut()
+            .enumerate()
+            .map(|(idx, value)| (idx.try_into().unwrap(), value))
+    }
 }
 
 impl<K, V> Index<K> for AdjacencyList<K, V>
@@ -85,7 +236,7 @@ where
 pub struct Iter<'a, K, V> {
     idx: usize,
     values: &'a Vec<V>,
-    _phantom: PhantomData<K>,
+    _phantom: PhantomData<fn() -> K>,
 }
 
 impl<'a, K, V> std::iter::IntoIterator for &'a AdjacencyList<K, V>
@@ -122,7 +273,8 @@ where
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.values.len(), Some(self.values.len()))
+        let len = self.values.len() - self.idx;
+        (len, Some(len))
     }
 }
 
@@ -131,7 +283,225 @@ where
     K: TryFrom<usize>,
     K::Error: std::fmt::Debug,
 {
-    fn len(&self) -> usize {
-        self.values.len()
+}
+
+pub struct IterMut<'a, K, V> {
+    values: std::iter::Enumerate<std::slice::IterMut<'a, V>>,
+    _phantom: PhantomData<fn() -> K>,
+}
+
+impl<'a, K, V> std::iter::IntoIterator for &'a mut AdjacencyList<K, V>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+    type Item = (K, &'a mut V);
+
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IterMut {
+            values: self.values.iter_mut().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values
+            .next()
+            .map(|(idx, value)| (idx.try_into().unwrap(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+}
+
+pub struct IntoIter<K, V> {
+    values: std::iter::Enumerate<std::vec::IntoIter<V>>,
+    _phantom: PhantomData<fn() -> K>,
+}
+
+impl<K, V> std::iter::IntoIterator for AdjacencyList<K, V>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+    type Item = (K, V);
+
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            values: self.values.into_iter().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values
+            .next()
+            .map(|(idx, value)| (idx.try_into().unwrap(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+}
+
+pub struct Keys<K> {
+    range: std::ops::Range<usize>,
+    _phantom: PhantomData<fn() -> K>,
+}
+
+impl<K> Iterator for Keys<K>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|idx| idx.try_into().unwrap())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<K> ExactSizeIterator for Keys<K>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ids::PlayerID;
+
+    use super::AdjacencyList;
+
+    #[test]
+    fn checked_access() {
+        let mut list = AdjacencyList::<PlayerID, u8>::from_vec(vec![1, 2]);
+        assert_eq!(list.len(), 2);
+        assert!(!list.is_empty());
+        assert_eq!(list.get(PlayerID(1)), Some(&2));
+        assert_eq!(list.get(PlayerID(2)), None);
+        assert!(list.contains_key(PlayerID(0)));
+        assert!(!list.contains_key(PlayerID(5)));
+
+        *list.get_mut(PlayerID(0)).unwrap() = 5;
+        assert_eq!(list.get_mut(PlayerID(2)), None);
+        assert_eq!(list[PlayerID(0)], 5);
+    }
+
+    #[test]
+    fn iteration() {
+        let mut list = AdjacencyList::<PlayerID, u8>::from_vec(vec![1, 2, 3]);
+
+        for (player, value) in list.iter_mut() {
+            *value += player.get();
+        }
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            vec![(PlayerID(0), &1), (PlayerID(1), &3), (PlayerID(2), &5)]
+        );
+
+        let keys = list.keys();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys.collect::<Vec<_>>(), vec![PlayerID(0), PlayerID(1), PlayerID(2)]);
+
+        let mut iter = list.iter();
+        iter.next();
+        assert_eq!(iter.len(), 2);
+
+        list.values_mut().for_each(|value| *value = 0);
+        assert!(list.values().all(|value| *value == 0));
+        assert!(AdjacencyList::<PlayerID, u8>::new().is_empty());
+    }
+
+    #[test]
+    fn owned_iteration_and_drain() {
+        let list = AdjacencyList::<PlayerID, String>::from_vec(vec!["a".into(), "b".into()]);
+        assert_eq!(
+            list.into_iter().collect::<Vec<_>>(),
+            vec![(PlayerID(0), "a".to_owned()), (PlayerID(1), "b".to_owned())]
+        );
+
+        let mut list = AdjacencyList::<PlayerID, String>::from_vec(vec!["c".into()]);
+        let drained: Vec<_> = list.drain().collect();
+        assert_eq!(drained, vec![(PlayerID(0), "c".to_owned())]);
+        assert!(list.is_empty());
+        assert_eq!(list.push("d".into()), PlayerID(0));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn parallel_iteration() {
+        use rayon::prelude::*;
+
+        let mut list = AdjacencyList::<PlayerID, u32>::from_vec((0..200).collect());
+        list.par_iter_mut().for_each(|(player, value)| *value += player.get() as u32);
+        let sum: u32 = list.par_iter().map(|(_, value)| *value).sum();
+        assert_eq!(sum, 2 * (0..200).sum::<u32>());
+        assert_eq!(list.par_iter().len(), 200);
+    }
+
+    #[test]
+    fn zipped_iteration() {
+        let a = AdjacencyList::<PlayerID, u8>::from_vec(vec![1, 2]);
+        let b = AdjacencyList::<PlayerID, char>::from_vec(vec!['a', 'b']);
+        let c = AdjacencyList::<PlayerID, bool>::from_vec(vec![true, false]);
+
+        assert_eq!(
+            a.zip(&b).collect::<Vec<_>>(),
+            vec![(PlayerID(0), &1, &'a'), (PlayerID(1), &2, &'b')]
+        );
+        assert_eq!(
+            a.zip3(&b, &c).collect::<Vec<_>>(),
+            vec![(PlayerID(0), &1, &'a', &true), (PlayerID(1), &2, &'b', &false)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn zip_of_different_lengths_panics_in_debug() {
+        let a = AdjacencyList::<PlayerID, u8>::from_vec(vec![1, 2]);
+        let b = AdjacencyList::<PlayerID, u8>::from_vec(vec![1]);
+        let _ = a.zip(&b);
     }
 }
@@ -1,4 +1,9 @@
-use std::mem::MaybeUninit;
+use std::{marker::PhantomData, mem::MaybeUninit};
+
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 /// Vec with a backing array (with const size `N`) as a storage.
 /// It is useful when you want vec-like semantics of pushing values to the end of the
@@ -21,9 +26,19 @@ impl<T, const N: usize> ArrayVec<T, N> {
 
     /// Will panic if size exceeds capacity
     pub fn push(&mut self, value: T) {
-        assert!(self.size < N);
+        if self.try_push(value).is_err() {
+            panic!("ArrayVec capacity of {} exceeded", N);
+        }
+    }
+
+    /// Push the value to the end of the vec, or give it back
+    /// if the vec is already full.
+    pub fn try_push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
         // SAFETY: 
-        //  - self.size is less than N, which means accessing the self.storage[self.size]
+        //  - self.size is less than N (the vec is not full), which means accessing the self.storage[self.size]
         //    is safe. There is no possibility for self.size to be modified concurrently
         //    since we are holding an exclusive reference to self.
         //  - .write(value) is safe since there is no way for this memory location to be
@@ -34,6 +49,95 @@ impl<T, const N: usize> ArrayVec<T, N> {
             self.storage.get_unchecked_mut(self.size).write(value);
         }
         self.size += 1;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.size == N
+    }
+
+    /// The maximum amount of values the vec can hold
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The amount of values which can still be pushed into the vec
+    pub fn remaining_capacity(&self) -> usize {
+        N - self.size
+    }
+
+    /// Remove the last value from the vec and return it, if there is any
+    pub fn pop(&mut self) -> Option<T> {
+        if self.size == 0 {
+            return None;
+        }
+        self.size -= 1;
+        // SAFETY:
+        //  - Value at the (old) self.size - 1 is initialized. After decrementing
+        //    the size, it is no longer considered part of the vec, so it is read
+        //    out exactly once and won't be dropped by the vec again.
+        Some(unsafe { self.storage.get_unchecked(self.size).assume_init_read() })
+    }
+
+    /// Remove the value at the index, shifting all of the values after it
+    /// to the left. Will panic if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.size, "removal index {} is out of bounds of {}", index, self.size);
+        // SAFETY:
+        //  - index is less than self.size, so the value at it is initialized, and
+        //    it is read out exactly once, since it is immediately overwritten by
+        //    the values shifted from the right.
+        //  - Values in the range index + 1..self.size are initialized, and are moved
+        //    (bitwise) one place to the left. The last slot is then considered
+        //    uninitialized by decrementing the size, so nothing is dropped twice.
+        unsafe {
+            let ptr = self.storage.as_mut_ptr().add(index);
+            let value = (*ptr).assume_init_read();
+            std::ptr::copy(ptr.add(1), ptr, self.size - index - 1);
+            self.size -= 1;
+            value
+        }
+    }
+
+    /// Remove the value at the index, replacing it with the last value of the vec.
+    /// Does not preserve ordering, but is O(1). Will panic if the index is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.size, "removal index {} is out of bounds of {}", index, self.size);
+        let last = self.size - 1;
+        self.as_mut().swap(index, last);
+        self.pop().unwrap()
+    }
+
+    /// Drop all of the values in the vec
+    pub fn clear(&mut self) {
+        let values: *mut [T] = self.as_mut();
+        // Reset the size first, so that if dropping one of the values panics
+        // the vec won't try to drop them again.
+        self.size = 0;
+        // SAFETY:
+        //  - values points to the initialized prefix of the storage, which we
+        //    hold the exclusive reference to. The vec no longer considers them
+        //    initialized, so they are dropped exactly once.
+        unsafe { std::ptr::drop_in_place(values) };
+    }
+}
+
+/// The error returned from [`ArrayVec::try_push`] when the vec is full.
+/// Contains the value which was not pushed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T>(pub T);
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -59,6 +163,50 @@ impl<T, const N: usize> Drop for ArrayVec<T, N> {
     }
 }
 
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        self.into_iter().cloned().collect()
+    }
+}
+
+impl<T: std::hash::Hash, const N: usize> std::hash::Hash for ArrayVec<T, N> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<T: Serialize, const N: usize> Serialize for ArrayVec<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for ArrayVec<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ArrayVecVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVecVisitor<T, N> {
+            type Value = ArrayVec<T, N>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "a sequence of at most {} elements", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut vec = ArrayVec::new();
+                while let Some(value) = seq.next_element()? {
+                    if vec.try_push(value).is_err() {
+                        return Err(A::Error::invalid_length(N + 1, &self));
+                    }
+                }
+                Ok(vec)
+            }
+        }
+
+        deserializer.deserialize_seq(ArrayVecVisitor(PhantomData))
+    }
+}
+
 impl<T: PartialEq, const N: usize> PartialEq for ArrayVec<T, N> {
     fn eq(&self, other: &Self) -> bool {
         self.as_ref() == other.as_ref()
@@ -95,7 +243,7 @@ impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
     type IntoIter = std::slice::Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.as_ref().into_iter()
+        self.as_ref().iter()
     }
 }
 
@@ -105,7 +253,85 @@ impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayVec<T, N> {
     type IntoIter = std::slice::IterMut<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.as_mut().into_iter()
+        self.as_mut().iter_mut()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayVec<T, N> {
+    type Item = T;
+
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let vec = std::mem::ManuallyDrop::new(self);
+        IntoIter {
+            // SAFETY: The vec is never dropped, so the ownership of the initialized
+            // values in the storage is transferred to the iterator.
+            storage: unsafe { std::ptr::read(&vec.storage) },
+            start: 0,
+            end: vec.size,
+        }
+    }
+}
+
+/// Owning iterator over the values of ArrayVec.
+/// Values which were not yielded are dropped together with the iterator.
+pub struct IntoIter<T, const N: usize> {
+    storage: [MaybeUninit<T>; N],
+    /// Values in the range start..end are initialized and not yet yielded
+    start: usize,
+    end: usize,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        // SAFETY: Value at the (old) start is initialized, and is no longer
+        // in the range of values owned by the iterator, so it is read exactly once.
+        Some(unsafe { self.storage.get_unchecked(self.start - 1).assume_init_read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        // SAFETY: Same as in next, but from the other end of the range.
+        Some(unsafe { self.storage.get_unchecked(self.end).assume_init_read() })
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        let remaining = &mut self.storage[self.start..self.end] as *mut [MaybeUninit<T>] as *mut [T];
+        // Mark everything as yielded first, in case dropping one of the values panics
+        self.start = self.end;
+        // SAFETY: Values in the range start..end were initialized and not yielded,
+        // and MaybeUninit<T> has the same layout as T.
+        unsafe { std::ptr::drop_in_place(remaining) };
+    }
+}
+
+/// Will panic if the vec overflows its capacity, the same as push does
+impl<T, const N: usize> Extend<T> for ArrayVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
     }
 }
 
@@ -119,6 +345,7 @@ impl<A, const N: usize> FromIterator<A> for ArrayVec<A, N> {
     }
 }
 
+#[cfg(test)]
 macro_rules! array_vec {
     ($($items: expr),*) => {{
         let mut vec = $crate::array_vec::ArrayVec::new();
@@ -127,8 +354,151 @@ macro_rules! array_vec {
     }};
 }
 
+#[cfg(test)]
 pub(crate) use array_vec;
 
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::{ArrayVec, CapacityError};
+
+    #[test]
+    fn try_push_until_full() {
+        let mut vec = ArrayVec::<u8, 3>::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.capacity(), 3);
+
+        assert_eq!(vec.try_push(1), Ok(()));
+        assert_eq!(vec.try_push(2), Ok(()));
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec.remaining_capacity(), 1);
+        assert!(!vec.is_full());
+
+        assert_eq!(vec.try_push(3), Ok(()));
+        assert!(vec.is_full());
+        assert_eq!(vec.try_push(4), Err(CapacityError(4)));
+        assert_eq!(vec.as_ref(), &[1, 2, 3]);
+    }
+
+    /// Value which counts how many times it was dropped
+    struct DropCounter<'a>(&'a Cell<usize>);
+
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn pop_remove_and_swap_remove() {
+        let mut vec: ArrayVec<u8, 5> = [1, 2, 3, 4, 5].into_iter().collect();
+        assert_eq!(vec.pop(), Some(5));
+        assert_eq!(vec.remove(1), 2);
+        assert_eq!(vec.as_ref(), &[1, 3, 4]);
+        assert_eq!(vec.swap_remove(0), 1);
+        assert_eq!(vec.as_ref(), &[4, 3]);
+        assert_eq!(vec.remove(1), 3);
+        assert_eq!(vec.pop(), Some(4));
+        assert_eq!(vec.pop(), None);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_out_of_bounds_panics() {
+        let mut vec: ArrayVec<u8, 3> = [1, 2].into_iter().collect();
+        vec.remove(2);
+    }
+
+    #[test]
+    fn removals_drop_every_value_once() {
+        let drops = Cell::new(0);
+        let mut vec = ArrayVec::<_, 6>::new();
+        for _ in 0..6 {
+            vec.push(DropCounter(&drops));
+        }
+
+        drop(vec.pop());
+        assert_eq!(drops.get(), 1);
+        drop(vec.remove(0));
+        assert_eq!(drops.get(), 2);
+        drop(vec.swap_remove(1));
+        assert_eq!(drops.get(), 3);
+        assert_eq!(vec.len(), 3);
+
+        vec.clear();
+        assert_eq!(drops.get(), 6);
+        assert!(vec.is_empty());
+
+        vec.push(DropCounter(&drops));
+        vec.push(DropCounter(&drops));
+        drop(vec);
+        assert_eq!(drops.get(), 8);
+    }
+
+    #[test]
+    fn clone_and_hash_only_initialized_values() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |value: &dyn Fn(&mut DefaultHasher)| {
+            let mut hasher = DefaultHasher::new();
+            value(&mut hasher);
+            hasher.finish()
+        };
+
+        let vec: ArrayVec<String, 3> = ["a".to_owned(), "b".to_owned()].into_iter().collect();
+        let cloned = vec.clone();
+        assert_eq!(vec, cloned);
+        assert_eq!(hash(&|h| vec.hash(h)), hash(&|h| ["a", "b"][..].hash(h)));
+        assert_eq!(hash(&|h| vec.hash(h)), hash(&|h| cloned.hash(h)));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let vec: ArrayVec<u16, 3> = [1, 2].into_iter().collect();
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, "[1,2]");
+        assert_eq!(serde_json::from_str::<ArrayVec<u16, 3>>(&json).unwrap(), vec);
+        assert!(serde_json::from_str::<ArrayVec<u16, 3>>("[1,2,3,4]").is_err());
+    }
+
+    #[test]
+    fn owning_iteration_and_extend() {
+        let mut vec: ArrayVec<String, 4> = ["a", "b"].map(str::to_owned).into_iter().collect();
+        vec.extend(["c".to_owned()]);
+
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back().as_deref(), Some("c"));
+        let rest: ArrayVec<_, 4> = iter.collect();
+        assert_eq!(rest.as_ref(), &["a", "b"]);
+    }
+
+    #[test]
+    fn partially_consumed_iterator_drops_the_rest() {
+        let drops = Cell::new(0);
+        let mut vec = ArrayVec::<_, 4>::new();
+        vec.extend((0..4).map(|_| DropCounter(&drops)));
+
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(drops.get(), 2);
+        drop(iter);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_over_capacity_panics() {
+        let mut vec = ArrayVec::<u8, 1>::new();
+        vec.push(1);
+        vec.push(2);
+    }
+}
+
 // pub struct IterMut<'a, T> {
 //     current: NonNull<T>,
 //     end: *const T,
@@ -0,0 +1,218 @@
+use std::fmt::Write;
+
+use crate::{
+    ids::{PlayerID, TileID},
+    relations::GameState,
+    types::{HexSide, HexVertex, SettlePlace, TileTerrain},
+};
+
+/// Width of the single tile cell in characters. Odd rows are shifted half a
+/// cell to the right, the same way they are in the offset coordinates.
+const CELL_WIDTH: usize = 14;
+
+/// Vertices and sides of the tile in the order they are drawn, clockwise
+/// from the north
+const VERTICES: [HexVertex; 6] = [
+    HexVertex::North,
+    HexVertex::NorthEast,
+    HexVertex::SouthEast,
+    HexVertex::South,
+    HexVertex::SouthWest,
+    HexVertex::NorthWest,
+];
+const SIDES: [HexSide; 6] = [
+    HexSide::NorthEast,
+    HexSide::East,
+    HexSide::SouthEast,
+    HexSide::SouthWest,
+    HexSide::West,
+    HexSide::NorthWest,
+];
+
+/// Render the board as ASCII art, for debugging purposes.
+///
+/// Every tile is drawn as `[Te id mk]`, where `Te` is the abbreviation of the
+/// terrain, `id` is the TileID and `mk` is the value of the dice marker laid
+/// on it (`-` if there is none). The tile the robber is on is marked with
+/// `R`. Positions which are not occupied by any tile are drawn as water (`~`).
+///
+/// Rows with any pieces on them are followed by the line of the pieces around
+/// each of the tiles: `vvvvvv|ssssss`, with the vertices and the sides of the
+/// tile listed clockwise from the north. Settlements are drawn as the letter
+/// of their owner (`a` for PlayerID(0), `b` for PlayerID(1) and so on), towns
+/// as the capital letter, and roads as the PlayerID of their owner. Empty
+/// spots are drawn as `.`.
+///
+/// ```text
+///       ~         [Mo 0 5 R]     [Pa 1 2]
+///               a..B..|0..... ......|......
+///           [Fi 2 6]      [De 3 -]         ~
+///        .B....|...... .....B|......
+/// ```
+pub fn render_ascii(state: &GameState) -> String {
+    let positions = &state.tile.position;
+    let width = positions.values().map(|[x, _]| *x as usize + 1).max().unwrap_or(0);
+    let height = positions.values().map(|[_, y]| *y as usize + 1).max().unwrap_or(0);
+
+    let mut grid = vec![vec![None; width]; height];
+    for (tile, [x, y]) in positions {
+        grid[*y as usize][*x as usize] = Some(tile);
+    }
+
+    let mut out = String::new();
+    for (y, row) in grid.into_iter().enumerate() {
+        let indent = if y % 2 == 1 { " ".repeat(CELL_WIDTH / 2) } else { String::new() };
+        let mut line = indent.clone();
+        let mut pieces_line = indent;
+        let mut has_pieces = false;
+        for cell in row {
+            let (cell, pieces) = match cell {
+                Some(tile) => {
+                    let pieces = pieces_around(state, tile);
+                    has_pieces |= pieces.chars().any(|piece| !matches!(piece, '.' | '|'));
+                    (tile_cell(state, tile), pieces)
+                }
+                None => ("~".to_owned(), String::new()),
+            };
+            write!(line, "{:^width$}", cell, width = CELL_WIDTH).unwrap();
+            write!(pieces_line, "{:^width$}", pieces, width = CELL_WIDTH).unwrap();
+        }
+        writeln!(out, "{}", line.trim_end()).unwrap();
+        if has_pieces {
+            writeln!(out, "{}", pieces_line.trim_end()).unwrap();
+        }
+    }
+    out
+}
+
+fn tile_cell(state: &GameState, tile: TileID) -> String {
+    let marker = match state.tile.dice_marker[tile] {
+        Some(marker) => marker.value().to_string(),
+        None => "-".to_owned(),
+    };
+    let robber = if state.robber == Some(tile) { " R" } else { "" };
+    format!(
+        "[{} {} {}{}]",
+        terrain_abbreviation(state.tile.resource[tile]),
+        tile.get(),
+        marker,
+        robber
+    )
+}
+
+/// Pieces on the vertices and on the sides of the tile, as `vvvvvv|ssssss`
+fn pieces_around(state: &GameState, tile: TileID) -> String {
+    let settle_places = &state.tile.settle_places[tile];
+    let roads = &state.tile.roads[tile];
+    let vertices = VERTICES.iter().map(|vertex| {
+        match state.settle_place.occupancy[settle_places[*vertex]] {
+            SettlePlace::Empty => '.',
+            SettlePlace::Settlement(owner) => player_letter(owner),
+            SettlePlace::Town(owner) => player_letter(owner).to_ascii_uppercase(),
+        }
+    });
+    let sides = SIDES.iter().map(|side| match state.road.owner[roads[*side]] {
+        Some(owner) => char::from_digit(owner.get().into(), 36).unwrap_or('?'),
+        None => '.',
+    });
+    vertices.chain(['|']).chain(sides).collect()
+}
+
+fn player_letter(player: PlayerID) -> char {
+    char::from_digit(u32::from(player.get()) + 10, 36).unwrap_or('?')
+}
+
+fn terrain_abbreviation(terrain: TileTerrain) -> &'static str {
+    match terrain {
+        TileTerrain::Field => "Fi",
+        TileTerrain::Pasture => "Pa",
+        TileTerrain::Forest => "Fo",
+        TileTerrain::Mesa => "Me",
+        TileTerrain::Mountains => "Mo",
+        TileTerrain::Desert => "De",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::{PlayerID, TileID},
+        types::{HexSide, HexVertex, SettlePlace},
+        MapConfig, TileMap, TileTerrain,
+    };
+
+    use super::render_ascii;
+
+    #[test]
+    fn render_small_map() {
+        let config = MapConfig {
+            tile_bank: TileMap {
+                desert: 1,
+                mountains: 1,
+                pasture: 1,
+                field: 1,
+                ..Default::default()
+            },
+            map_size: [3, 2],
+            tile_placement: vec![[1, 0], [2, 0], [0, 1], [1, 1]],
+            default_tiles: vec![
+                TileTerrain::Mountains,
+                TileTerrain::Pasture,
+                TileTerrain::Field,
+                TileTerrain::Desert,
+            ],
+            fixed_tiles: TileMap::default(),
+            harbour_placement: vec![],
+            default_harbours: vec![],
+            default_dice_markers: vec![],
+            robber_tile: None,
+        };
+
+        let mut state = decode_config(config, 2).unwrap();
+
+        assert_eq!(
+            render_ascii(&state),
+            concat!(
+                "      ~          [Mo 0 5]      [Pa 1 2]\n",
+                "          [Fi 2 6]     [De 3 - R]        ~\n",
+            )
+        );
+
+        state.robber = Some(TileID(0));
+        assert!(render_ascii(&state).contains("[Mo 0 5 R]"));
+        assert!(render_ascii(&state).contains("[De 3 -]"));
+
+        // Settlement of the first player at the north of the mountains, with
+        // the road to the north east of it, and the town of the second player
+        // between the mountains, the field and the desert
+        let mountains = TileID(0);
+        let north = state.tile.settle_places[mountains][HexVertex::North];
+        state.settle_place.occupancy[north] = SettlePlace::Settlement(PlayerID(0));
+        let road = state.tile.roads[mountains][HexSide::NorthEast];
+        state.road.owner[road] = Some(PlayerID(0));
+        let south = state.tile.settle_places[mountains][HexVertex::South];
+        state.settle_place.occupancy[south] = SettlePlace::Town(PlayerID(1));
+        assert_eq!(
+            render_ascii(&state),
+            concat!(
+                "      ~         [Mo 0 5 R]     [Pa 1 2]\n",
+                "              a..B..|0..... ......|......\n",
+                "          [Fi 2 6]      [De 3 -]         ~\n",
+                "       .B....|...... .....B|......\n",
+            )
+        );
+    }
+
+    #[test]
+    fn render_default_map() {
+        let config = MapConfig::standard();
+        let state = decode_config(config, 4).unwrap();
+        let rendered = render_ascii(&state);
+
+        assert_eq!(rendered.lines().count(), 6);
+        assert_eq!(rendered.matches('[').count(), 19);
+        assert!(rendered.contains("[De 9 - R]"));
+        assert_eq!(rendered.matches(" R]").count(), 1);
+    }
+}
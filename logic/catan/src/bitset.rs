@@ -0,0 +1,206 @@
+use std::marker::PhantomData;
+
+/// Fixed-size set of the IDs of type K, storing a single bit per ID.
+///
+/// Like [`crate::adjacency_list::AdjacencyList`], keys must be convertible
+/// from and to usize.
+#[derive(PartialEq, Eq, Hash)]
+pub struct BitSet<K> {
+    words: Vec<u64>,
+    capacity: usize,
+    _phantom: PhantomData<fn() -> K>,
+}
+
+impl<K> Clone for BitSet<K> {
+    fn clone(&self) -> Self {
+        Self {
+            words: self.words.clone(),
+            capacity: self.capacity,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.words.clone_from(&source.words);
+        self.capacity = source.capacity;
+    }
+}
+
+impl<K> std::fmt::Debug for BitSet<K>
+where
+    K: TryFrom<usize> + std::fmt::Debug,
+    K::Error: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<K> BitSet<K> {
+    /// Set of none of the IDs below `capacity`
+    pub fn empty(capacity: usize) -> Self {
+        Self {
+            words: vec![0; capacity.div_ceil(64)],
+            capacity,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Set of all of the IDs below `capacity`
+    pub fn full(capacity: usize) -> Self {
+        let mut set = Self {
+            words: vec![u64::MAX; capacity.div_ceil(64)],
+            capacity,
+            _phantom: PhantomData,
+        };
+        set.clear_tail();
+        set
+    }
+
+    /// Amount of the IDs the set can hold: every ID below it
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Amount of the IDs in the set
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Keep only the IDs which are in both of the sets
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.combine(other, |a, b| a & b);
+    }
+
+    /// Add all of the IDs of the other set
+    pub fn union_with(&mut self, other: &Self) {
+        self.combine(other, |a, b| a | b);
+    }
+
+    /// Remove all of the IDs of the other set
+    pub fn difference_with(&mut self, other: &Self) {
+        self.combine(other, |a, b| a & !b);
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+    }
+
+    /// Panics if the sets are not of the same capacity
+    fn combine(&mut self, other: &Self, op: impl Fn(u64, u64) -> u64) {
+        assert_eq!(self.capacity, other.capacity, "sets are of different capacities");
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a = op(*a, *b);
+        }
+    }
+
+    fn clear_tail(&mut self) {
+        if let Some(last) = self.words.last_mut() {
+            let tail = self.capacity % 64;
+            if tail != 0 {
+                *last &= (1 << tail) - 1;
+            }
+        }
+    }
+}
+
+impl<K: Into<usize>> BitSet<K> {
+    /// Panics if the ID is not below the capacity of the set
+    pub fn contains(&self, key: K) -> bool {
+        let idx = self.check(key);
+        self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    /// Add the ID to the set, returning whether it wasn't there before
+    pub fn insert(&mut self, key: K) -> bool {
+        let idx = self.check(key);
+        let word = &mut self.words[idx / 64];
+        let was_absent = *word & (1 << (idx % 64)) == 0;
+        *word |= 1 << (idx % 64);
+        was_absent
+    }
+
+    /// Remove the ID from the set, returning whether it was there
+    pub fn remove(&mut self, key: K) -> bool {
+        let idx = self.check(key);
+        let word = &mut self.words[idx / 64];
+        let was_present = *word & (1 << (idx % 64)) != 0;
+        *word &= !(1 << (idx % 64));
+        was_present
+    }
+
+    fn check(&self, key: K) -> usize {
+        let idx = key.into();
+        assert!(idx < self.capacity, "ID {} is out of the set of {} IDs", idx, self.capacity);
+        idx
+    }
+}
+
+impl<K> BitSet<K>
+where
+    K: TryFrom<usize>,
+    K::Error: std::fmt::Debug,
+{
+    /// Iterate over the IDs in the set, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_idx, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some((word_idx * 64 + bit).try_into().unwrap())
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ids::RoadID;
+
+    use super::BitSet;
+
+    #[test]
+    fn set_operations() {
+        let mut set = BitSet::<RoadID>::empty(130);
+        assert!(set.is_empty());
+        assert_eq!(set.len(), 0);
+        assert_eq!(set.capacity(), 130);
+        assert!(set.insert(RoadID(3)));
+        assert!(!set.insert(RoadID(3)));
+        assert!(set.insert(RoadID(64)));
+        assert!(set.insert(RoadID(129)));
+        assert!(set.contains(RoadID(64)));
+        assert!(!set.contains(RoadID(65)));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [RoadID(3), RoadID(64), RoadID(129)]);
+
+        let full = BitSet::<RoadID>::full(130);
+        assert_eq!(full.len(), 130);
+        let mut rest = full.clone();
+        rest.difference_with(&set);
+        assert_eq!(rest.len(), 127);
+        assert!(rest.is_disjoint(&set));
+
+        rest.union_with(&set);
+        assert_eq!(rest, full);
+        rest.intersect_with(&set);
+        assert_eq!(rest, set);
+
+        assert!(set.remove(RoadID(64)));
+        assert!(!set.remove(RoadID(64)));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ids_out_of_the_set_panic() {
+        BitSet::<RoadID>::full(10).contains(RoadID(10));
+    }
+}
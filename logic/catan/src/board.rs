@@ -0,0 +1,477 @@
+use crate::{
+    ids::{CoordInt, DiceMarkerID, PlayerID, ResourceTileID, RoadID, SettlePlaceID, TileID},
+    relations::{GameState, SettleRelations},
+    resolve_harbour,
+    types::{DiceMarker, Harbour, HarbourPlacement, HexSide, HexVertex, SettlePlace, TileTerrain},
+    MapConfigError,
+};
+
+/// Read-only queries over the board of the game.
+///
+/// Unlike the relations in [`crate::unstable`], the queries do not expose
+/// how the state is stored, so they are a part of the stable API. Every
+/// query panics if the ID does not belong to the board, the same way
+/// indexing out of bounds does.
+#[derive(Debug, Clone, Copy)]
+pub struct Board<'a> {
+    state: &'a GameState,
+}
+
+impl GameState {
+    /// Query the board of the game
+    pub fn board(&self) -> Board<'_> {
+        Board { state: self }
+    }
+
+    /// [`Board::production_value`] of every settle place, for the callers
+    /// looking them up over and over (e.g. the balance checks and the bots).
+    /// The values have to be computed anew after the robber moves.
+    pub fn production_values(&self) -> SettleRelations<u8> {
+        let board = self.board();
+        SettleRelations::from_vec(
+            board
+                .settle_places()
+                .map(|settle_place| board.production_value(settle_place))
+                .collect(),
+        )
+    }
+}
+
+impl<'a> Board<'a> {
+    /// All of the tiles of the board, in the order of their IDs
+    pub fn tiles(&self) -> impl ExactSizeIterator<Item = TileID> + 'a {
+        self.state.tile.resource.keys()
+    }
+
+    /// All of the roads of the board, in the order of their IDs
+    pub fn roads(&self) -> impl ExactSizeIterator<Item = RoadID> + 'a {
+        self.state.road.settle_places.keys()
+    }
+
+    /// All of the settle places of the board, in the order of their IDs
+    pub fn settle_places(&self) -> impl ExactSizeIterator<Item = SettlePlaceID> + 'a {
+        self.state.settle_place.roads.keys()
+    }
+
+    pub fn terrain(&self, tile: TileID) -> TileTerrain {
+        self.state.tile.resource[tile]
+    }
+
+    /// The tile the robber is on, or None while it is off the board
+    pub fn robber(&self) -> Option<TileID> {
+        self.state.robber
+    }
+
+    /// Tiles producing resources (every tile, except for the deserts),
+    /// in the order of their IDs
+    pub fn resource_tiles(&self) -> impl ExactSizeIterator<Item = ResourceTileID> + 'a {
+        self.state.resource_tile.tile.keys()
+    }
+
+    /// ID of the tile among the resource tiles. Deserts have none.
+    pub fn resource_tile(&self, tile: TileID) -> Option<ResourceTileID> {
+        self.state.tile.resource_tile[tile]
+    }
+
+    /// The tile of the board, which is the resource tile
+    pub fn tile_of_resource(&self, resource_tile: ResourceTileID) -> TileID {
+        self.state.resource_tile.tile[resource_tile]
+    }
+
+    /// All of the dice markers laid on the board, in the order of their IDs
+    pub fn dice_markers(&self) -> impl ExactSizeIterator<Item = DiceMarkerID> + 'a {
+        self.state.dice_marker.values.keys()
+    }
+
+    /// The number written on the dice marker
+    pub fn marker_value(&self, marker: DiceMarkerID) -> DiceMarker {
+        self.state.dice_marker.values[marker]
+    }
+
+    /// The tile the dice marker is laid on
+    pub fn tile_of_marker(&self, marker: DiceMarkerID) -> TileID {
+        self.tile_of_resource(self.state.dice_marker.place[marker])
+    }
+
+    /// Dice marker laid on the tile. Deserts have none.
+    pub fn dice_marker(&self, tile: TileID) -> Option<DiceMarker> {
+        self.state.tile.dice_marker[tile]
+    }
+
+    /// Total pips of the markers on the tiles around the settle place: the
+    /// amount of the outcomes of the dice roll out of 36, for which the
+    /// settlement there produces, counted once per each producing tile.
+    /// The robber is not taken into account, see [`Board::production_value`].
+    pub fn pips(&self, settle_place: SettlePlaceID) -> u32 {
+        self.tiles_of(settle_place)
+            .iter()
+            .filter_map(|tile| self.dice_marker(*tile))
+            .map(|marker| u32::from(marker.pips()))
+            .sum()
+    }
+
+    /// Total pips of the markers on the tiles around the settle place, except
+    /// for the tile the robber is on, since it does not produce anything. At
+    /// most three tiles of five pips each, so it always fits the u8.
+    pub fn production_value(&self, settle_place: SettlePlaceID) -> u8 {
+        self.tiles_of(settle_place)
+            .iter()
+            .filter(|tile| self.robber() != Some(**tile))
+            .filter_map(|tile| self.dice_marker(*tile))
+            .map(|marker| marker.pips())
+            .sum()
+    }
+
+    /// Tiles producing resources when the number of the marker is rolled,
+    /// in the order of their IDs
+    pub fn tiles_with_marker(&self, marker: DiceMarker) -> impl Iterator<Item = TileID> + 'a {
+        let board = *self;
+        self.dice_markers()
+            .filter(move |id| board.marker_value(*id) == marker)
+            .map(move |id| board.tile_of_marker(id))
+    }
+
+    /// Offset coordinates of the tile, as specified in the map config
+    pub fn position(&self, tile: TileID) -> [CoordInt; 2] {
+        self.state.tile.position[tile]
+    }
+
+    /// The tile at the offset coordinates, if there is any
+    pub fn tile_at(&self, position: [CoordInt; 2]) -> Option<TileID> {
+        self.state.tile.grid.get(position).copied().flatten()
+    }
+
+    /// Harbours of the board with both of the settle places they serve, in
+    /// the order of [`crate::MapConfig::harbour_placement`]
+    pub fn harbours(&self) -> impl ExactSizeIterator<Item = (Harbour, [SettlePlaceID; 2])> + 'a {
+        let harbour = &self.state.harbour;
+        harbour.kind.iter().copied().zip(harbour.settle_places.iter().copied())
+    }
+
+    /// Both of the settle places the harbour would serve, which are the ends
+    /// of the side of the tile the harbour faces. The harbour has to be in the
+    /// water (or beyond the map), facing one of the tiles of the board.
+    pub fn resolve_harbour(
+        &self,
+        harbour: HarbourPlacement,
+    ) -> Result<[SettlePlaceID; 2], MapConfigError> {
+        resolve_harbour(&self.state.tile, harbour)
+    }
+
+    /// Tiles laying next to the sides of the tile
+    pub fn neighbors(&self, tile: TileID) -> impl Iterator<Item = (HexSide, TileID)> + 'a {
+        self.state.tile.neighbors[tile]
+            .into_iter()
+            .filter_map(|(side, neighbor)| Some((side, neighbor?)))
+    }
+
+    /// Groups of tiles connected to each other by their sides. Islands are
+    /// ordered by their lowest TileID, and the tiles within them by their IDs.
+    pub fn islands(&self) -> Vec<Vec<TileID>> {
+        let mut island_of = vec![None; self.tiles().len()];
+        let mut islands: Vec<Vec<TileID>> = Vec::new();
+        for seed in self.tiles() {
+            if island_of[usize::from(seed)].is_some() {
+                continue;
+            }
+            let island = islands.len();
+            island_of[usize::from(seed)] = Some(island);
+            let mut tiles = vec![seed];
+            let mut next = 0;
+            while let Some(&tile) = tiles.get(next) {
+                next += 1;
+                for (_, neighbor) in self.neighbors(tile) {
+                    if island_of[usize::from(neighbor)].is_none() {
+                        island_of[usize::from(neighbor)] = Some(island);
+                        tiles.push(neighbor);
+                    }
+                }
+            }
+            tiles.sort_by_key(|tile| usize::from(*tile));
+            islands.push(tiles);
+        }
+        islands
+    }
+
+    /// Roads laying on each of the sides of the tile
+    pub fn roads_of_tile(&self, tile: TileID) -> impl Iterator<Item = (HexSide, RoadID)> + 'a {
+        self.state.tile.roads[tile].into_iter()
+    }
+
+    /// Settle places at each of the vertices of the tile
+    pub fn settle_places_of_tile(
+        &self,
+        tile: TileID,
+    ) -> impl Iterator<Item = (HexVertex, SettlePlaceID)> + 'a {
+        self.state.tile.settle_places[tile].into_iter()
+    }
+
+    /// Roads coming out of the settle place
+    pub fn roads_of(&self, settle_place: SettlePlaceID) -> &'a [RoadID] {
+        self.state.settle_place.roads[settle_place].as_ref()
+    }
+
+    /// Tiles the settle place is a vertex of
+    pub fn tiles_of(&self, settle_place: SettlePlaceID) -> &'a [TileID] {
+        self.state.settle_place.tiles[settle_place].as_ref()
+    }
+
+    /// Both of the settle places the road connects
+    pub fn ends_of(&self, road: RoadID) -> [SettlePlaceID; 2] {
+        self.state.road.settle_places[road]
+    }
+
+    /// Settle places a single road away from the settle place
+    pub fn adjacent_settle_places(
+        &self,
+        settle_place: SettlePlaceID,
+    ) -> impl Iterator<Item = SettlePlaceID> + 'a {
+        let board = *self;
+        self.roads_of(settle_place).iter().map(move |road| {
+            let [a, b] = board.ends_of(*road);
+            if a == settle_place {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    /// The player which has placed the road, if any
+    pub fn owner_of(&self, road: RoadID) -> Option<PlayerID> {
+        self.state.road.owner[road]
+    }
+
+    /// What is built on the settle place
+    pub fn occupancy(&self, settle_place: SettlePlaceID) -> SettlePlace {
+        self.state.settle_place.occupancy[settle_place]
+    }
+
+    /// The player which has settled the settle place with either
+    /// a settlement or a town, if any
+    pub fn settler_of(&self, settle_place: SettlePlaceID) -> Option<PlayerID> {
+        match self.occupancy(settle_place) {
+            SettlePlace::Settlement(player) | SettlePlace::Town(player) => Some(player),
+            SettlePlace::Empty => None,
+        }
+    }
+
+    /// See [`GameState::is_connected`]
+    pub fn is_connected(&self, player: PlayerID, road: RoadID) -> bool {
+        self.state.is_connected(player, road)
+    }
+
+    /// See [`GameState::longest_road`]
+    pub fn longest_road(&self, player: PlayerID) -> usize {
+        self.state.longest_road(player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::{CoordInt, PlayerID, ResourceTileID, RoadID, SettlePlaceID, TileID},
+        types::{DiceMarker, HarbourPlacement, HexSide, SettlePlace, TileTerrain},
+        DecodeConfigError, MapConfig, MapConfigBuilder, MapConfigError,
+    };
+
+    #[test]
+    fn queries_agree_with_each_other() {
+        let state = decode_config(MapConfig::standard(), 4).unwrap();
+        let board = state.board();
+
+        assert_eq!(board.tiles().len(), 19);
+        assert_eq!(board.roads().len(), 72);
+        assert_eq!(board.settle_places().len(), 54);
+        assert_eq!(board.terrain(TileID(9)), TileTerrain::Desert);
+        assert_eq!(board.position(TileID(9)), [3, 3]);
+        assert_eq!(board.robber(), Some(TileID(9)));
+        // The central tile is surrounded from every side
+        assert_eq!(board.neighbors(TileID(9)).count(), 6);
+
+        for tile in board.tiles() {
+            for (vertex, settle_place) in board.settle_places_of_tile(tile) {
+                assert!(board.tiles_of(settle_place).contains(&tile), "{vertex:?}");
+            }
+            for (side, neighbor) in board.neighbors(tile) {
+                let opposite = board
+                    .roads_of_tile(neighbor)
+                    .find(|(neighbor_side, _)| *neighbor_side == side.opposite())
+                    .map(|(_, road)| road);
+                let own = board
+                    .roads_of_tile(tile)
+                    .find(|(own_side, _)| *own_side == side)
+                    .map(|(_, road)| road);
+                assert_eq!(own, opposite);
+            }
+        }
+
+        for settle_place in board.settle_places() {
+            for adjacent in board.adjacent_settle_places(settle_place) {
+                assert!(board.adjacent_settle_places(adjacent).any(|s| s == settle_place));
+            }
+        }
+        let [a, b] = board.ends_of(RoadID(0));
+        assert!(board.roads_of(a).contains(&RoadID(0)));
+        assert!(board.roads_of(b).contains(&RoadID(0)));
+        assert!(board.neighbors(TileID(0)).all(|(side, _)| side != HexSide::NorthWest));
+    }
+
+    #[test]
+    fn dice_markers_of_the_tiles() {
+        let state = decode_config(MapConfig::standard(), 2).unwrap();
+        let board = state.board();
+        assert_eq!(board.dice_marker(TileID(9)), None);
+        assert_eq!(board.tiles().filter(|tile| board.dice_marker(*tile).is_some()).count(), 18);
+        for marker in DiceMarker::ALL {
+            let tiles: Vec<_> = board.tiles_with_marker(marker).collect();
+            let expected = match marker {
+                DiceMarker::Two | DiceMarker::Twelve => 1,
+                _ => 2,
+            };
+            assert_eq!(tiles.len(), expected, "{marker:?}");
+            assert!(tiles.iter().all(|tile| board.dice_marker(*tile) == Some(marker)));
+        }
+    }
+
+    #[test]
+    fn robbed_tiles_do_not_produce() {
+        let mut state = decode_config(MapConfig::standard(), 2).unwrap();
+        let board = state.board();
+        // The robber starts on the desert, which has no marker anyway
+        for settle_place in board.settle_places() {
+            assert_eq!(u32::from(board.production_value(settle_place)), board.pips(settle_place));
+        }
+
+        let robbed = TileID(0);
+        let marker = board.dice_marker(robbed).unwrap();
+        state.robber = Some(robbed);
+        let board = state.board();
+        let values = state.production_values();
+        assert_eq!(values.len(), 54);
+        for settle_place in board.settle_places() {
+            let expected = match board.tiles_of(settle_place).contains(&robbed) {
+                true => board.pips(settle_place) - u32::from(marker.pips()),
+                false => board.pips(settle_place),
+            };
+            assert_eq!(u32::from(board.production_value(settle_place)), expected);
+            assert_eq!(values[settle_place], board.production_value(settle_place));
+        }
+    }
+
+    #[test]
+    fn resource_tiles_skip_the_deserts() {
+        let state = decode_config(MapConfig::standard(), 2).unwrap();
+        let board = state.board();
+        assert_eq!(board.resource_tiles().len(), 18);
+        assert_eq!(board.resource_tile(TileID(8)), Some(ResourceTileID(8)));
+        assert_eq!(board.resource_tile(TileID(9)), None);
+        assert_eq!(board.resource_tile(TileID(10)), Some(ResourceTileID(9)));
+        for resource_tile in board.resource_tiles() {
+            let tile = board.tile_of_resource(resource_tile);
+            assert_ne!(board.terrain(tile), TileTerrain::Desert);
+            assert_eq!(board.resource_tile(tile), Some(resource_tile));
+        }
+
+        assert_eq!(board.dice_markers().len(), 18);
+        for marker in board.dice_markers() {
+            let tile = board.tile_of_marker(marker);
+            assert_eq!(board.dice_marker(tile), Some(board.marker_value(marker)));
+        }
+    }
+
+    #[test]
+    fn harbours_serve_the_ends_of_the_tile_side() {
+        let config = MapConfig::standard();
+        let state = decode_config(config.clone(), 2).unwrap();
+        let board = state.board();
+
+        assert_eq!(board.harbours().len(), config.harbour_placement().len());
+        let mut served = Vec::new();
+        let placed = config.harbour_placement().iter().zip(&config.default_harbours);
+        for ((kind, [a, b]), (placement, default)) in board.harbours().zip(placed) {
+            assert_eq!(kind, *default);
+            assert_eq!(board.resolve_harbour(*placement), Ok([a, b]));
+            assert!(board.adjacent_settle_places(a).any(|adjacent| adjacent == b));
+            // Harbours are on the coast, so the settle places border the sea
+            assert!(board.tiles_of(a).len() < 3 && board.tiles_of(b).len() < 3);
+            served.extend([a, b]);
+        }
+        served.sort_by_key(|settle_place| usize::from(*settle_place));
+        served.dedup();
+        assert_eq!(served.len(), 18);
+
+        let in_land = HarbourPlacement::new([3, 3], HexSide::East);
+        assert_eq!(
+            board.resolve_harbour(in_land),
+            Err(MapConfigError::HarbourNotOnShore([3, 3]))
+        );
+        let facing_water = HarbourPlacement::new([2, 0], HexSide::NorthEast);
+        assert_eq!(
+            board.resolve_harbour(facing_water),
+            Err(MapConfigError::HarbourNotFacingTile([2, 0]))
+        );
+        assert_eq!(board.tile_at([3, 3]), Some(TileID(9)));
+        assert_eq!(board.tile_at([0, 0]), None);
+        assert_eq!(board.tile_at([CoordInt::MAX, 0]), None);
+    }
+
+    #[test]
+    fn harbours_are_validated_when_decoding() {
+        let misplaced = |placement| {
+            let mut config = MapConfig::standard();
+            config.harbour_placement[0] = placement;
+            decode_config(config, 2).map(|_| ())
+        };
+        assert_eq!(
+            misplaced(HarbourPlacement::new([3, 3], HexSide::East)),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::HarbourNotOnShore([3, 3])))
+        );
+        assert_eq!(
+            misplaced(HarbourPlacement::new([2, 0], HexSide::NorthEast)),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::HarbourNotFacingTile([2, 0])))
+        );
+    }
+
+    #[test]
+    fn islands_of_the_board() {
+        let state = decode_config(MapConfig::standard(), 2).unwrap();
+        assert_eq!(state.board().islands(), [state.board().tiles().collect::<Vec<_>>()]);
+
+        let config = MapConfigBuilder::new()
+            .add_tile([1, 1], TileTerrain::Forest)
+            .add_tile([7, 7], TileTerrain::Field)
+            .add_tile([2, 1], TileTerrain::Mesa)
+            .add_tile([7, 6], TileTerrain::Pasture)
+            .add_tile([4, 4], TileTerrain::Desert)
+            .build()
+            .unwrap();
+        let state = decode_config(config, 2).unwrap();
+        assert_eq!(
+            state.board().islands(),
+            [vec![TileID(0), TileID(2)], vec![TileID(1), TileID(3)], vec![TileID(4)]]
+        );
+    }
+
+    #[test]
+    fn owners_of_pieces() {
+        let mut state = decode_config(MapConfig::standard(), 2).unwrap();
+        assert!(state.board().roads().all(|road| state.board().owner_of(road).is_none()));
+        assert!(state
+            .board()
+            .settle_places()
+            .all(|settle_place| state.board().occupancy(settle_place) == SettlePlace::Empty));
+
+        state.road.owner[RoadID(3)] = Some(PlayerID(1));
+        state.settle_place.occupancy[SettlePlaceID(2)] = SettlePlace::Town(PlayerID(1));
+        state.settle_place.occupancy[SettlePlaceID(4)] = SettlePlace::Settlement(PlayerID(0));
+
+        let board = state.board();
+        assert_eq!(board.owner_of(RoadID(3)), Some(PlayerID(1)));
+        assert_eq!(board.owner_of(RoadID(4)), None);
+        assert_eq!(board.settler_of(SettlePlaceID(2)), Some(PlayerID(1)));
+        assert_eq!(board.settler_of(SettlePlaceID(4)), Some(PlayerID(0)));
+        assert_eq!(board.settler_of(SettlePlaceID(5)), None);
+    }
+}
@@ -0,0 +1,736 @@
+use std::{collections::BTreeSet, io::Read};
+
+use crate::{
+    grid::neighbor_positions,
+    ids::{CoordInt, TileID, TileInt},
+    types::{DiceMarker, Harbour, HarbourPlacement, HexSide, TileTerrain},
+    MapConfig, TileMap,
+};
+
+/// Most cells the map may have. The decoding lays the whole map out in
+/// memory, so the size is limited even if the coordinates allow more.
+pub const MAX_MAP_AREA: usize = 1 << 20;
+
+/// Reasons the map config can be rejected before it is decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum MapConfigError {
+    /// There are no tiles placed on the map
+    #[error("there are no tiles placed on the map")]
+    EmptyMap,
+    /// Either the width or the height of the map is zero
+    #[error("map of size {0:?} has no room for tiles")]
+    ZeroSizedMap([CoordInt; 2]),
+    /// The tile is placed outside of the map size
+    #[error("tile at {0:?} is outside of the map")]
+    TileOutOfBounds([CoordInt; 2]),
+    /// More than one tile is placed at the same position
+    #[error("more than one tile is placed at {0:?}")]
+    DuplicateTile([CoordInt; 2]),
+    /// There are more tiles than TileID can address
+    #[error("{0} tiles is more than TileID can address")]
+    TooManyTiles(usize),
+    /// The map has more than [`MAX_MAP_AREA`] cells
+    #[error("map of size {0:?} is too large")]
+    MapTooLarge([CoordInt; 2]),
+    /// Amount of the default tiles differs from the amount of placed tiles
+    #[error("{placed} tiles are placed, but {default} default tiles are given")]
+    DefaultTilesMismatch { placed: usize, default: usize },
+    /// Amount of tiles of the terrain in the tile bank differs from the
+    /// amount of such tiles in the default distribution
+    #[error("tile bank does not match the default distribution of {0:?} tiles")]
+    TileBankMismatch(TileTerrain),
+    /// Fixed TileID does not refer to any of the placed tiles
+    #[error("fixed tile {0:?} is not one of the placed tiles")]
+    UnknownFixedTile(TileID),
+    /// Fixed tile is of a different terrain in the default distribution
+    #[error("fixed tile {0:?} differs from its default terrain")]
+    FixedTileMismatch(TileID),
+    /// Harbour is not placed in the water next to any of the tiles
+    #[error("harbour at {0:?} is not on the shore")]
+    HarbourNotOnShore([CoordInt; 2]),
+    /// Harbour is on the shore, but its side faces the water instead of a tile
+    #[error("harbour at {0:?} does not face any of the tiles")]
+    HarbourNotFacingTile([CoordInt; 2]),
+    /// Amount of the default harbours differs from the amount of placed harbours
+    #[error("{placed} harbours are placed, but {default} default harbours are given")]
+    DefaultHarboursMismatch { placed: usize, default: usize },
+    /// Dice markers are specified, but not one per each of the placed tiles
+    #[error("{placed} tiles are placed, but {default} default dice markers are given")]
+    DefaultDiceMarkersMismatch { placed: usize, default: usize },
+    /// Dice marker is laid on the desert, which never produces resources
+    #[error("dice marker is laid on the desert tile {0:?}")]
+    DiceMarkerOnDesert(TileID),
+    /// Tile producing resources has no dice marker
+    #[error("resource tile {0:?} has no dice marker")]
+    MissingDiceMarker(TileID),
+    /// The robber starts on the tile which is not one of the placed tiles
+    #[error("robber starts on {0:?}, which is not one of the placed tiles")]
+    UnknownRobberTile(TileID),
+    /// The robber starts on the tile which is not a desert
+    #[error("robber starts on {0:?}, which is not a desert")]
+    RobberNotOnDesert(TileID),
+}
+
+/// Reasons the map config could not be loaded
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum LoadConfigError {
+    #[error("could not read the map config")]
+    Io(#[source] std::io::Error),
+    #[error("could not parse the map config as JSON")]
+    Json(#[source] serde_json::Error),
+    #[cfg(feature = "ron")]
+    #[error("could not parse the map config as RON")]
+    Ron(#[source] ron::error::SpannedError),
+    #[cfg(feature = "toml")]
+    #[error("could not parse the map config as TOML")]
+    Toml(#[source] toml::de::Error),
+    /// The config was parsed, but it contradicts itself
+    #[error("map config is invalid")]
+    Invalid(#[from] MapConfigError),
+}
+
+/// Parse and validate the map config in the JSON format
+pub fn load_json(text: &str) -> Result<MapConfig, LoadConfigError> {
+    let config: MapConfig = serde_json::from_str(text).map_err(LoadConfigError::Json)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Parse and validate the map config in the RON format. Field names are the
+/// same as in JSON, and the structs may be written without their names:
+///
+/// ```ron
+/// (
+///     tileBank: (desert: 1),
+///     mapSize: (1, 1),
+///     tilePlacement: [(0, 0)],
+///     defaultTiles: [desert],
+///     harbourPlacement: [],
+///     defaultHarbours: [],
+/// )
+/// ```
+#[cfg(feature = "ron")]
+pub fn load_ron(text: &str) -> Result<MapConfig, LoadConfigError> {
+    // IDs are written as plain numbers, the same as they are in JSON
+    let config: MapConfig = ron::Options::default()
+        .with_default_extension(ron::extensions::Extensions::UNWRAP_NEWTYPES)
+        .from_str(text)
+        .map_err(LoadConfigError::Ron)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Parse and validate the map config in the TOML format. Field names are
+/// the same as in JSON.
+#[cfg(feature = "toml")]
+pub fn load_toml(text: &str) -> Result<MapConfig, LoadConfigError> {
+    let config: MapConfig = toml::from_str(text).map_err(LoadConfigError::Toml)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// The standard map of the base game, as shipped in `maps/default.json`
+pub const STANDARD_MAP: &str = include_str!("../../../maps/default.json");
+
+impl MapConfig {
+    /// The standard map of the base game
+    pub fn standard() -> Self {
+        load_json(STANDARD_MAP).expect("standard map is a valid config")
+    }
+
+    /// Read, parse and validate the map config in the JSON format
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, LoadConfigError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).map_err(LoadConfigError::Io)?;
+        load_json(&text)
+    }
+
+    /// Serialize the config into its canonical JSON form: pretty-printed,
+    /// with every field present, in the order of their declaration, and
+    /// a trailing newline. Configs which are equal serialize to the same
+    /// text, so saved maps can be diffed and compared byte-wise.
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("MapConfig is always serializable");
+        json.push('\n');
+        json
+    }
+
+    /// Check that the config describes a consistent map. This does not
+    /// guarantee the map can be decoded, only that the config does not
+    /// contradict itself.
+    pub fn validate(&self) -> Result<(), MapConfigError> {
+        use MapConfigError::*;
+
+        let tile_count = self.tile_placement.len();
+        if tile_count == 0 {
+            return Err(EmptyMap);
+        }
+        if self.map_size.contains(&0) {
+            return Err(ZeroSizedMap(self.map_size));
+        }
+        if tile_count > TileID::MAX as usize + 1 {
+            return Err(TooManyTiles(tile_count));
+        }
+        if self.map_size[0] as usize * self.map_size[1] as usize > MAX_MAP_AREA {
+            return Err(MapTooLarge(self.map_size));
+        }
+
+        let mut positions = BTreeSet::new();
+        for &position @ [x, y] in &self.tile_placement {
+            if x >= self.map_size[0] || y >= self.map_size[1] {
+                return Err(TileOutOfBounds(position));
+            }
+            if !positions.insert(position) {
+                return Err(DuplicateTile(position));
+            }
+        }
+
+        if self.default_tiles.len() != tile_count {
+            return Err(DefaultTilesMismatch {
+                placed: tile_count,
+                default: self.default_tiles.len(),
+            });
+        }
+
+        let mut counts = TileMap::<usize>::default();
+        for terrain in &self.default_tiles {
+            counts[*terrain] += 1;
+        }
+        for terrain in TileTerrain::ALL {
+            if counts[terrain] != self.tile_bank[terrain] as usize {
+                return Err(TileBankMismatch(terrain));
+            }
+        }
+
+        for terrain in TileTerrain::ALL {
+            for tile in &self.fixed_tiles[terrain] {
+                if usize::from(*tile) >= tile_count {
+                    return Err(UnknownFixedTile(*tile));
+                }
+                if self.default_tiles[usize::from(*tile)] != terrain {
+                    return Err(FixedTileMismatch(*tile));
+                }
+            }
+        }
+
+        for harbour in &self.harbour_placement {
+            let on_shore = neighbor_positions(harbour.position)
+                .values()
+                .flatten()
+                .any(|neighbor| positions.contains(neighbor));
+            if positions.contains(&harbour.position) || !on_shore {
+                return Err(HarbourNotOnShore(harbour.position));
+            }
+            if !harbour.tile_position().is_some_and(|tile| positions.contains(&tile)) {
+                return Err(HarbourNotFacingTile(harbour.position));
+            }
+        }
+
+        if self.default_harbours.len() != self.harbour_placement.len() {
+            return Err(DefaultHarboursMismatch {
+                placed: self.harbour_placement.len(),
+                default: self.default_harbours.len(),
+            });
+        }
+
+        if !self.default_dice_markers.is_empty() {
+            validate_dice_markers(&self.default_tiles, &self.default_dice_markers)?;
+        }
+
+        if let Some(tile) = self.robber_tile {
+            match self.default_tiles.get(usize::from(tile)) {
+                None => return Err(UnknownRobberTile(tile)),
+                Some(TileTerrain::Desert) => {}
+                Some(_) => return Err(RobberNotOnDesert(tile)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Positions of the harbours, in the order of their IDs
+    pub fn harbour_placement(&self) -> &[HarbourPlacement] {
+        &self.harbour_placement
+    }
+
+    /// The desert the robber starts on, when the rules place it on a desert:
+    /// either the one specified by the config, or the first of the deserts.
+    /// None if there are no deserts on the map.
+    pub fn robber_tile(&self) -> Option<TileID> {
+        self.robber_tile.or_else(|| {
+            let idx = self
+                .default_tiles
+                .iter()
+                .position(|terrain| *terrain == TileTerrain::Desert)?;
+            Some(TileID(idx as TileInt))
+        })
+    }
+
+    /// Dice markers laid on each of the tiles, either the ones specified by
+    /// the config, or the markers of the base game laid out one per each of
+    /// the resource tiles, in the order of their IDs. On maps with more than
+    /// 18 resource tiles the base game markers are repeated.
+    pub fn dice_markers(&self) -> Vec<Option<DiceMarker>> {
+        if !self.default_dice_markers.is_empty() {
+            return self.default_dice_markers.clone();
+        }
+        let mut markers = DiceMarker::BASE_GAME.into_iter().cycle();
+        self.default_tiles
+            .iter()
+            .map(|terrain| match terrain {
+                TileTerrain::Desert => None,
+                _ => markers.next(),
+            })
+            .collect()
+    }
+}
+
+/// Check that there is exactly one dice marker per each of the tiles
+/// producing resources, and none on the deserts. Markers are indexed by
+/// TileID, so none of them can be laid out in the sea.
+pub fn validate_dice_markers(
+    tiles: &[TileTerrain],
+    markers: &[Option<DiceMarker>],
+) -> Result<(), MapConfigError> {
+    use MapConfigError::*;
+
+    if markers.len() != tiles.len() {
+        return Err(DefaultDiceMarkersMismatch {
+            placed: tiles.len(),
+            default: markers.len(),
+        });
+    }
+    for (idx, (terrain, marker)) in tiles.iter().zip(markers).enumerate() {
+        let tile = TileID(idx as TileInt);
+        match (terrain, marker) {
+            (TileTerrain::Desert, Some(_)) => return Err(DiceMarkerOnDesert(tile)),
+            (TileTerrain::Desert, None) | (_, Some(_)) => {}
+            (_, None) => return Err(MissingDiceMarker(tile)),
+        }
+    }
+    Ok(())
+}
+
+/// Incrementally construct the [`MapConfig`] in code.
+///
+/// Tiles and harbours are given IDs in the order they are added. Unless
+/// specified explicitly, the tile bank is counted from the added tiles, and
+/// the map size is the smallest one which fits all of the tiles.
+///
+/// ```
+/// use catan::{config::MapConfigBuilder, types::{Harbour, HexSide, TileTerrain}};
+///
+/// let config = MapConfigBuilder::new()
+///     .add_tile([0, 0], TileTerrain::Field)
+///     .add_tile([1, 0], TileTerrain::Desert)
+///     .add_harbour([2, 0], HexSide::West, Harbour::Universal)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MapConfigBuilder {
+    tile_bank: Option<TileMap<TileInt>>,
+    map_size: Option<[CoordInt; 2]>,
+    tiles: Vec<([CoordInt; 2], TileTerrain)>,
+    fixed_tiles: TileMap<Vec<TileID>>,
+    harbours: Vec<(HarbourPlacement, Harbour)>,
+    dice_markers: Vec<Option<DiceMarker>>,
+    robber_tile: Option<TileID>,
+}
+
+impl MapConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Place the tile of the terrain at the offset coordinates
+    pub fn add_tile(mut self, position: [CoordInt; 2], terrain: TileTerrain) -> Self {
+        self.tiles.push((position, terrain));
+        self
+    }
+
+    /// Place the tile, which keeps its terrain even if the map is randomized
+    pub fn add_fixed_tile(mut self, position: [CoordInt; 2], terrain: TileTerrain) -> Self {
+        let id = TileID::try_from(self.tiles.len()).unwrap_or(TileID(TileID::MAX));
+        self.fixed_tiles[terrain].push(id);
+        self.add_tile(position, terrain)
+    }
+
+    /// Override the tile bank, instead of counting it from the added tiles
+    pub fn set_bank(mut self, tile_bank: TileMap<TileInt>) -> Self {
+        self.tile_bank = Some(tile_bank);
+        self
+    }
+
+    /// Override the map size, instead of fitting it to the added tiles
+    pub fn set_map_size(mut self, map_size: [CoordInt; 2]) -> Self {
+        self.map_size = Some(map_size);
+        self
+    }
+
+    /// Place the harbour in the water at the offset coordinates, facing
+    /// the tile on the specified side
+    pub fn add_harbour(mut self, position: [CoordInt; 2], side: HexSide, kind: Harbour) -> Self {
+        self.harbours.push((HarbourPlacement::new(position, side), kind));
+        self
+    }
+
+    /// Lay the dice markers on the tiles, one per each of the added tiles
+    /// in the order they were added. Unless specified, the markers of the
+    /// base game are laid out instead.
+    pub fn set_dice_markers(mut self, markers: Vec<Option<DiceMarker>>) -> Self {
+        self.dice_markers = markers;
+        self
+    }
+
+    /// Start the robber on the desert tile with the ID, instead of the first
+    /// of the deserts
+    pub fn set_robber_tile(mut self, tile: TileID) -> Self {
+        self.robber_tile = Some(tile);
+        self
+    }
+
+    /// Assemble and validate the config
+    pub fn build(self) -> Result<MapConfig, MapConfigError> {
+        let map_size = self.map_size.unwrap_or_else(|| {
+            self.tiles.iter().fold([0, 0], |[width, height], ([x, y], _)| {
+                [width.max(x.saturating_add(1)), height.max(y.saturating_add(1))]
+            })
+        });
+        let tile_bank = match self.tile_bank {
+            Some(bank) => bank,
+            None => {
+                let mut bank = TileMap::<TileInt>::default();
+                for (_, terrain) in &self.tiles {
+                    // Every TileID fits the TileInt with room to spare
+                    bank[*terrain] = bank[*terrain]
+                        .checked_add(1)
+                        .ok_or(MapConfigError::TooManyTiles(self.tiles.len()))?;
+                }
+                bank
+            }
+        };
+        let (tile_placement, default_tiles) = self.tiles.into_iter().unzip();
+        let (harbour_placement, default_harbours) = self.harbours.into_iter().unzip();
+
+        let config = MapConfig {
+            tile_bank,
+            map_size,
+            tile_placement,
+            default_tiles,
+            fixed_tiles: self.fixed_tiles,
+            harbour_placement,
+            default_harbours,
+            default_dice_markers: self.dice_markers,
+            robber_tile: self.robber_tile,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::{CoordInt, TileID},
+        types::{DiceMarker, Harbour, HexSide, TileTerrain},
+        MapConfig, TileMap,
+    };
+
+    use super::{
+        load_json, validate_dice_markers, LoadConfigError, MapConfigBuilder, MapConfigError,
+        STANDARD_MAP,
+    };
+
+    #[test]
+    fn builds_the_same_config_as_written_by_hand() {
+        let config = MapConfigBuilder::new()
+            .set_map_size([3, 2])
+            .add_tile([1, 0], TileTerrain::Mountains)
+            .add_tile([2, 0], TileTerrain::Pasture)
+            .add_fixed_tile([0, 1], TileTerrain::Field)
+            .add_tile([1, 1], TileTerrain::Desert)
+            .add_harbour([2, 1], HexSide::NorthWest, Harbour::Sheep)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            config,
+            MapConfig {
+                tile_bank: TileMap {
+                    desert: 1,
+                    mountains: 1,
+                    pasture: 1,
+                    field: 1,
+                    ..Default::default()
+                },
+                map_size: [3, 2],
+                tile_placement: vec![[1, 0], [2, 0], [0, 1], [1, 1]],
+                default_tiles: vec![
+                    TileTerrain::Mountains,
+                    TileTerrain::Pasture,
+                    TileTerrain::Field,
+                    TileTerrain::Desert,
+                ],
+                fixed_tiles: TileMap {
+                    field: vec![TileID(2)],
+                    ..Default::default()
+                },
+                harbour_placement: vec![crate::types::HarbourPlacement {
+                    position: [2, 1],
+                    side: HexSide::NorthWest,
+                }],
+                default_harbours: vec![Harbour::Sheep],
+                default_dice_markers: vec![],
+                robber_tile: None,
+            }
+        );
+        decode_config(config, 2).unwrap();
+    }
+
+    #[test]
+    fn map_size_fits_the_tiles() {
+        let config = MapConfigBuilder::new()
+            .add_tile([4, 1], TileTerrain::Forest)
+            .add_tile([0, 2], TileTerrain::Forest)
+            .build()
+            .unwrap();
+        assert_eq!(config.map_size, [5, 3]);
+        assert_eq!(config.tile_bank.forest, 2);
+    }
+
+    #[test]
+    fn rejects_inconsistent_configs() {
+        let duplicate = MapConfigBuilder::new()
+            .add_tile([1, 1], TileTerrain::Forest)
+            .add_tile([1, 1], TileTerrain::Mesa)
+            .build();
+        assert_eq!(duplicate, Err(MapConfigError::DuplicateTile([1, 1])));
+
+        let out_of_bounds = MapConfigBuilder::new()
+            .set_map_size([2, 2])
+            .add_tile([2, 0], TileTerrain::Forest)
+            .build();
+        assert_eq!(out_of_bounds, Err(MapConfigError::TileOutOfBounds([2, 0])));
+
+        let bank = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .set_bank(TileMap {
+                mesa: 1,
+                ..Default::default()
+            })
+            .build();
+        assert_eq!(bank, Err(MapConfigError::TileBankMismatch(TileTerrain::Forest)));
+
+        // Every TileID is taken by the forest, and then one more tile
+        let forest = |tiles: usize| {
+            (0..tiles)
+                .fold(MapConfigBuilder::new(), |builder, idx| {
+                    let position = [(idx % 256) as CoordInt, (idx / 256) as CoordInt];
+                    builder.add_tile(position, TileTerrain::Forest)
+                })
+                .build()
+        };
+        let tiles = TileID::MAX as usize + 1;
+        assert_eq!(forest(tiles).unwrap().tile_bank[TileTerrain::Forest] as usize, tiles);
+        assert_eq!(forest(tiles + 1), Err(MapConfigError::TooManyTiles(tiles + 1)));
+
+        let harbour = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .add_harbour([0, 0], HexSide::East, Harbour::Universal)
+            .build();
+        assert_eq!(harbour, Err(MapConfigError::HarbourNotOnShore([0, 0])));
+
+        let facing_water = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .add_harbour([1, 0], HexSide::East, Harbour::Universal)
+            .build();
+        assert_eq!(facing_water, Err(MapConfigError::HarbourNotFacingTile([1, 0])));
+
+        let mut fixed = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .build()
+            .unwrap();
+        fixed.fixed_tiles.mesa.push(TileID(0));
+        assert_eq!(fixed.validate(), Err(MapConfigError::FixedTileMismatch(TileID(0))));
+    }
+
+    #[test]
+    fn dice_markers_match_the_resource_tiles() {
+        let builder = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .add_tile([1, 0], TileTerrain::Desert)
+            .add_tile([2, 0], TileTerrain::Mesa);
+        let config = builder
+            .clone()
+            .set_dice_markers(vec![Some(DiceMarker::Six), None, Some(DiceMarker::Two)])
+            .build()
+            .unwrap();
+        assert_eq!(config.dice_markers(), [Some(DiceMarker::Six), None, Some(DiceMarker::Two)]);
+        let json: serde_json::Value = serde_json::from_str(&config.to_json()).unwrap();
+        assert_eq!(json["defaultDiceMarkers"], serde_json::json!([6, null, 2]));
+
+        let too_few = builder.clone().set_dice_markers(vec![Some(DiceMarker::Six)]).build();
+        assert_eq!(
+            too_few,
+            Err(MapConfigError::DefaultDiceMarkersMismatch { placed: 3, default: 1 })
+        );
+        let on_desert = builder
+            .clone()
+            .set_dice_markers(vec![Some(DiceMarker::Six), Some(DiceMarker::Ten), Some(DiceMarker::Two)])
+            .build();
+        assert_eq!(on_desert, Err(MapConfigError::DiceMarkerOnDesert(TileID(1))));
+        let missing = builder
+            .set_dice_markers(vec![Some(DiceMarker::Six), None, None])
+            .build();
+        assert_eq!(missing, Err(MapConfigError::MissingDiceMarker(TileID(2))));
+    }
+
+    #[test]
+    fn base_game_dice_markers_are_generated() {
+        let config = MapConfig::standard();
+        let markers = config.dice_markers();
+        assert_eq!(validate_dice_markers(&config.default_tiles, &markers), Ok(()));
+        let mut generated: Vec<_> = markers.into_iter().flatten().collect();
+        let mut base_game = DiceMarker::BASE_GAME;
+        generated.sort_by_key(|marker| marker.value());
+        base_game.sort_by_key(|marker| marker.value());
+        assert_eq!(generated, base_game);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let config = MapConfig::standard();
+        let json = config.to_json();
+        let decoded: MapConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, config);
+        assert_eq!(decoded.to_json(), json);
+
+        let built = MapConfigBuilder::new()
+            .add_fixed_tile([0, 0], TileTerrain::Desert)
+            .add_tile([1, 0], TileTerrain::Mesa)
+            .add_harbour([1, 1], HexSide::NorthWest, Harbour::Brick)
+            .build()
+            .unwrap();
+        let decoded: MapConfig = serde_json::from_str(&built.to_json()).unwrap();
+        assert_eq!(decoded, built);
+    }
+
+    #[test]
+    fn canonical_json_lists_every_field() {
+        let config = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Field)
+            .build()
+            .unwrap();
+        let text = config.to_json();
+        let offsets: Vec<_> = [
+            "tileBank",
+            "mapSize",
+            "tilePlacement",
+            "defaultTiles",
+            "fixedTiles",
+            "harbourPlacement",
+            "defaultHarbours",
+            "defaultDiceMarkers",
+            "robberTile",
+        ]
+        .map(|key| text.find(&format!("\"{key}\":")).unwrap())
+        .to_vec();
+        assert!(offsets.is_sorted());
+
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json["fixedTiles"]["desert"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn loads_json() {
+        let config = MapConfig::from_reader(STANDARD_MAP.as_bytes()).unwrap();
+        assert_eq!(config, load_json(STANDARD_MAP).unwrap());
+        assert_eq!(config.tile_placement.len(), 19);
+
+        assert!(matches!(load_json("{"), Err(LoadConfigError::Json(_))));
+        let duplicate = r#"{
+            "tileBank": { "forest": 2 },
+            "mapSize": [1, 1],
+            "tilePlacement": [[0, 0], [0, 0]],
+            "defaultTiles": ["forest", "forest"],
+            "harbourPlacement": [],
+            "defaultHarbours": []
+        }"#;
+        assert!(matches!(
+            load_json(duplicate),
+            Err(LoadConfigError::Invalid(MapConfigError::DuplicateTile([0, 0])))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "ron")]
+    fn loads_ron() {
+        let config = super::load_ron(
+            r#"(
+                // A lonely island
+                tileBank: (desert: 1, field: 1),
+                mapSize: (2, 1),
+                tilePlacement: [(0, 0), (1, 0)],
+                defaultTiles: [desert, field],
+                fixedTiles: (desert: [0]),
+                harbourPlacement: [(position: (1, 1), side: nw)],
+                defaultHarbours: [universal],
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            MapConfigBuilder::new()
+                .add_fixed_tile([0, 0], TileTerrain::Desert)
+                .add_tile([1, 0], TileTerrain::Field)
+                .add_harbour([1, 1], HexSide::NorthWest, Harbour::Universal)
+                .build()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn loads_toml() {
+        let config = super::load_toml(
+            r#"
+            # A lonely island
+            mapSize = [2, 1]
+            tilePlacement = [[0, 0], [1, 0]]
+            defaultTiles = ["desert", "field"]
+            defaultHarbours = ["universal"]
+
+            [tileBank]
+            desert = 1
+            field = 1
+
+            [fixedTiles]
+            desert = [0]
+
+            [[harbourPlacement]]
+            position = [1, 1]
+            side = "nw"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            MapConfigBuilder::new()
+                .add_fixed_tile([0, 0], TileTerrain::Desert)
+                .add_tile([1, 0], TileTerrain::Field)
+                .add_harbour([1, 1], HexSide::NorthWest, Harbour::Universal)
+                .build()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn default_map_is_valid() {
+        let config = MapConfig::standard();
+        assert_eq!(config.validate(), Ok(()));
+    }
+}
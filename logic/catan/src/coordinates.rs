@@ -0,0 +1,276 @@
+use enum_map::{enum_map, EnumMap};
+
+use crate::{ids::CoordInt, types::HexSide};
+
+/// Axial coordinates of the (pointy-top) hex tile.
+///
+/// The map config stores tile positions as offset [x, y] coordinates, where
+/// each odd row is shifted half a tile to the east. Those are convenient for
+/// storage, but are terrible for doing any math upon. Axial coordinates on
+/// the other hand, make distances, rings and lines simple arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Axial {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// Cube coordinates of the hex tile. The invariant is that q + r + s == 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub q: i32,
+    pub r: i32,
+    pub s: i32,
+}
+
+impl Axial {
+    pub fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    /// Convert from the offset coordinates used in the MapConfig
+    pub fn from_offset([x, y]: [CoordInt; 2]) -> Self {
+        let (x, y) = (x as i32, y as i32);
+        Self {
+            q: x - (y - (y & 1)) / 2,
+            r: y,
+        }
+    }
+
+    /// Convert to the offset coordinates used in the MapConfig. Returns None
+    /// if the position is not representable by them (e.g. has negative components).
+    pub fn to_offset(self) -> Option<[CoordInt; 2]> {
+        let x = self.q + (self.r - (self.r & 1)) / 2;
+        Some([x.try_into().ok()?, self.r.try_into().ok()?])
+    }
+
+    pub fn to_cube(self) -> Cube {
+        Cube {
+            q: self.q,
+            r: self.r,
+            s: -self.q - self.r,
+        }
+    }
+
+    /// The tile next to this one, which lays on the specified side
+    pub fn neighbor(self, side: HexSide) -> Self {
+        self + directions()[side]
+    }
+
+    /// The amount of steps required to get from one tile to another
+    pub fn distance(self, other: Self) -> u32 {
+        self.to_cube().distance(other.to_cube())
+    }
+
+    /// All of the tiles exactly `radius` steps away from this one. Tiles are
+    /// listed clockwise, starting from the one `radius` steps to the west.
+    pub fn ring(self, radius: u32) -> Vec<Axial> {
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut tile = self + directions()[HexSide::West] * radius as i32;
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+        for side in [
+            HexSide::NorthEast,
+            HexSide::East,
+            HexSide::SouthEast,
+            HexSide::SouthWest,
+            HexSide::West,
+            HexSide::NorthWest,
+        ] {
+            for _ in 0..radius {
+                ring.push(tile);
+                tile = tile.neighbor(side);
+            }
+        }
+        ring
+    }
+
+    /// All of the tiles within `radius` steps from this one, including itself
+    pub fn spiral(self, radius: u32) -> Vec<Axial> {
+        (0..=radius).flat_map(|radius| self.ring(radius)).collect()
+    }
+
+    /// The tiles a straight line drawn from the center of this tile to the
+    /// center of the other one crosses, including both ends.
+    pub fn line_to(self, other: Self) -> Vec<Axial> {
+        let distance = self.distance(other);
+        if distance == 0 {
+            return vec![self];
+        }
+        let (from, to) = (self.to_cube(), other.to_cube());
+        // Nudge the line a bit, so it does not land exactly on the edges
+        // between tiles, which would make rounding ambiguous.
+        let lerp = |a: i32, b: i32, t: f64| a as f64 + (b - a) as f64 * t;
+        (0..=distance)
+            .map(|step| {
+                let t = step as f64 / distance as f64;
+                Cube::round(
+                    lerp(from.q, to.q, t) + 1e-6,
+                    lerp(from.r, to.r, t) + 1e-6,
+                    lerp(from.s, to.s, t) - 2e-6,
+                )
+                .to_axial()
+            })
+            .collect()
+    }
+}
+
+impl Cube {
+    pub fn to_axial(self) -> Axial {
+        Axial {
+            q: self.q,
+            r: self.r,
+        }
+    }
+
+    pub fn distance(self, other: Self) -> u32 {
+        let dq = self.q.abs_diff(other.q);
+        let dr = self.r.abs_diff(other.r);
+        let ds = self.s.abs_diff(other.s);
+        dq.max(dr).max(ds)
+    }
+
+    /// Round fractional cube coordinates to the nearest tile
+    fn round(q: f64, r: f64, s: f64) -> Self {
+        let (mut rq, mut rr, mut rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        } else {
+            rs = -rq - rr;
+        }
+        Self {
+            q: rq as i32,
+            r: rr as i32,
+            s: rs as i32,
+        }
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(axial: Axial) -> Self {
+        axial.to_cube()
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(cube: Cube) -> Self {
+        cube.to_axial()
+    }
+}
+
+impl std::ops::Add for Axial {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+        }
+    }
+}
+
+impl std::ops::Sub for Axial {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            q: self.q - rhs.q,
+            r: self.r - rhs.r,
+        }
+    }
+}
+
+impl std::ops::Mul<i32> for Axial {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        Self {
+            q: self.q * rhs,
+            r: self.r * rhs,
+        }
+    }
+}
+
+/// The offset in axial coordinates to the neighbor on each side
+pub fn directions() -> EnumMap<HexSide, Axial> {
+    enum_map! {
+        HexSide::NorthWest => Axial::new(0, -1),
+        HexSide::NorthEast => Axial::new(1, -1),
+        HexSide::West => Axial::new(-1, 0),
+        HexSide::East => Axial::new(1, 0),
+        HexSide::SouthWest => Axial::new(-1, 1),
+        HexSide::SouthEast => Axial::new(0, 1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{grid::neighbor_positions, types::HexSide};
+
+    use super::Axial;
+
+    #[test]
+    fn offset_round_trip() {
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(Axial::from_offset([x, y]).to_offset(), Some([x, y]));
+            }
+        }
+        assert_eq!(Axial::new(0, -1).to_offset(), None);
+    }
+
+    #[test]
+    fn neighbors_agree_with_offset_grid() {
+        for x in 1..7 {
+            for y in 1..7 {
+                let axial = Axial::from_offset([x, y]);
+                for (side, pos) in neighbor_positions([x, y]) {
+                    assert_eq!(axial.neighbor(side).to_offset(), pos);
+                    assert_eq!(axial.distance(axial.neighbor(side)), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distance() {
+        let origin = Axial::new(0, 0);
+        assert_eq!(origin.distance(origin), 0);
+        assert_eq!(origin.distance(Axial::new(3, -1)), 3);
+        assert_eq!(origin.distance(Axial::new(-2, 4)), 4);
+        assert_eq!(Axial::new(2, 1).distance(Axial::new(-1, 1)), 3);
+    }
+
+    #[test]
+    fn rings() {
+        let center = Axial::from_offset([3, 3]);
+        assert_eq!(center.ring(0), vec![center]);
+        for radius in 1..4 {
+            let ring = center.ring(radius);
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert!(ring.iter().all(|tile| tile.distance(center) == radius));
+        }
+        // The standard map is a hexagon of radius two around the desert
+        assert_eq!(center.spiral(2).len(), 19);
+        assert_eq!(center.ring(1)[0], center.neighbor(HexSide::West));
+    }
+
+    #[test]
+    fn lines() {
+        let from = Axial::new(0, 0);
+        let to = Axial::new(3, -3);
+        assert_eq!(
+            from.line_to(to),
+            vec![from, Axial::new(1, -1), Axial::new(2, -2), to]
+        );
+
+        let to = Axial::new(-4, 1);
+        let line = from.line_to(to);
+        assert_eq!(line.len(), 5);
+        assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+        assert_eq!(line.last(), Some(&to));
+    }
+}
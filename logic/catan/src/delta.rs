@@ -0,0 +1,473 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    adjacency_list::AdjacencyList,
+    ids::{PlayerID, RoadID, SettlePlaceID, TileID},
+    relations::GameState,
+    types::{PlayerHand, PlayerProfile, SettlePlace},
+};
+
+/// Changes between two versions of the same relation. Contains every
+/// mapping which differs in the newer version, as well as its length, so
+/// that the relations which were both grown or shrunk can be restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelationDelta<K, V> {
+    pub len: usize,
+    pub changed: Vec<(K, V)>,
+}
+
+impl<K, V> RelationDelta<K, V>
+where
+    K: TryFrom<usize> + Into<usize> + Copy,
+    K::Error: std::fmt::Debug,
+    V: PartialEq + Clone,
+{
+    /// Produce the changes required to turn `old` into `new`
+    pub fn between(old: &AdjacencyList<K, V>, new: &AdjacencyList<K, V>) -> Self {
+        let changed = new
+            .into_iter()
+            .filter(|(key, value)| {
+                let idx: usize = (*key).into();
+                idx >= old.len() || old[*key] != **value
+            })
+            .map(|(key, value)| (key, value.clone()))
+            .collect();
+
+        Self {
+            len: new.len(),
+            changed,
+        }
+    }
+
+    pub fn is_empty(&self, relation: &AdjacencyList<K, V>) -> bool {
+        self.changed.is_empty() && self.len == relation.len()
+    }
+
+    /// Check that the delta can be applied to the relation: the changed
+    /// keys are sorted, each of them is either within the relation or the
+    /// next one to be pushed, and they add up to the length of the delta.
+    pub fn validate(&self, relation: &AdjacencyList<K, V>) -> Result<(), DeltaError> {
+        let mut len = relation.len().min(self.len);
+        let mut previous = None;
+        for (key, _) in &self.changed {
+            let key: usize = (*key).into();
+            if previous.is_some_and(|previous| key <= previous) {
+                return Err(DeltaError::UnsortedKey(key));
+            }
+            match key.cmp(&len) {
+                Ordering::Less => {}
+                Ordering::Equal => len += 1,
+                Ordering::Greater => return Err(DeltaError::KeyOutOfRange { key, len }),
+            }
+            previous = Some(key);
+        }
+        if len != self.len {
+            return Err(DeltaError::LengthMismatch {
+                expected: self.len,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Apply the changes to the older version of the relation. The relation
+    /// is left untouched if the delta does not fit it.
+    pub fn apply(self, relation: &mut AdjacencyList<K, V>) -> Result<(), DeltaError> {
+        self.validate(relation)?;
+        self.apply_unchecked(relation);
+        Ok(())
+    }
+
+    /// Apply the changes validated by [`RelationDelta::validate`]. Changed
+    /// mappings are sorted by key, so the new ones are always pushed in the
+    /// correct order.
+    fn apply_unchecked(self, relation: &mut AdjacencyList<K, V>) {
+        relation.truncate(self.len);
+        for (key, value) in self.changed {
+            if key.into() < relation.len() {
+                relation[key] = value;
+            } else {
+                relation.push(value);
+            }
+        }
+    }
+}
+
+/// Reasons the delta could not be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum DeltaError {
+    /// Changed keys have to be in the strictly increasing order
+    #[error("changed key {0} is not after the previous one")]
+    UnsortedKey(usize),
+    /// The key is neither within the relation, nor the next one to be pushed
+    #[error("changed key {key} is past the end of the relation of length {len}")]
+    KeyOutOfRange { key: usize, len: usize },
+    /// The changes do not produce the relation of the length of the delta
+    #[error("delta of length {expected} would produce the relation of length {actual}")]
+    LengthMismatch { expected: usize, actual: usize },
+    /// Relations of the board topology (road owners and settle place
+    /// occupancy) have an entry for every road or settle place, and the
+    /// player relations for every player of the game, which can't be added
+    /// or removed
+    #[error("delta resizes the relation of fixed length {expected} to {actual}")]
+    FixedLength { expected: usize, actual: usize },
+    #[error("delta refers to unknown player {0:?}")]
+    UnknownPlayer(PlayerID),
+    #[error("delta refers to unknown road {0:?}")]
+    UnknownRoad(RoadID),
+    #[error("delta refers to unknown settle place {0:?}")]
+    UnknownSettlePlace(SettlePlaceID),
+    #[error("delta refers to unknown tile {0:?}")]
+    UnknownTile(TileID),
+}
+
+/// Changes to the properties of player entities
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerDelta {
+    pub placed_roads: RelationDelta<PlayerID, Vec<RoadID>>,
+    pub towns: RelationDelta<PlayerID, Vec<SettlePlaceID>>,
+    pub settlements: RelationDelta<PlayerID, Vec<SettlePlaceID>>,
+    pub hand: RelationDelta<PlayerID, PlayerHand>,
+    pub profile: RelationDelta<PlayerID, PlayerProfile>,
+}
+
+/// Changes to the properties of road entities
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoadDelta {
+    pub owner: RelationDelta<RoadID, Option<PlayerID>>,
+}
+
+/// Changes to the properties of settle place entities
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlePlaceDelta {
+    pub occupancy: RelationDelta<SettlePlaceID, SettlePlace>,
+}
+
+/// Everything which changed between two snapshots of the game state.
+///
+/// The board topology (tiles, roads and settle places and the relations
+/// between them) is fixed when the config is decoded, so only relations
+/// which change during the game are tracked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDelta {
+    pub player: PlayerDelta,
+    pub road: RoadDelta,
+    pub settle_place: SettlePlaceDelta,
+    /// The tile the robber was moved to (or Some(None) if it was taken off
+    /// the board), if it was moved at all
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    pub robber: Option<Option<TileID>>,
+}
+
+/// Deserialize the field which is present as Some, even if it is null, so
+/// that the absent field (None) and the null one (Some(None)) differ
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl GameState {
+    /// Produce the delta which turns this state into the other one
+    pub fn diff(&self, other: &GameState) -> StateDelta {
+        StateDelta {
+            player: PlayerDelta {
+                placed_roads: RelationDelta::between(
+                    &self.player.placed_roads,
+                    &other.player.placed_roads,
+                ),
+                towns: RelationDelta::between(&self.player.towns, &other.player.towns),
+                settlements: RelationDelta::between(
+                    &self.player.settlements,
+                    &other.player.settlements,
+                ),
+                hand: RelationDelta::between(&self.player.hand, &other.player.hand),
+                profile: RelationDelta::between(&self.player.profile, &other.player.profile),
+            },
+            road: RoadDelta {
+                owner: RelationDelta::between(&self.road.owner, &other.road.owner),
+            },
+            settle_place: SettlePlaceDelta {
+                occupancy: RelationDelta::between(
+                    &self.settle_place.occupancy,
+                    &other.settle_place.occupancy,
+                ),
+            },
+            robber: (self.robber != other.robber).then_some(other.robber),
+        }
+    }
+
+    /// Apply the delta produced by [`GameState::diff`] of this state. The
+    /// state is left untouched if the delta does not fit it.
+    pub fn apply_delta(&mut self, delta: StateDelta) -> Result<(), DeltaError> {
+        delta.validate(self)?;
+        let StateDelta {
+            player,
+            road,
+            settle_place,
+            robber,
+        } = delta;
+        player.placed_roads.apply_unchecked(&mut self.player.placed_roads);
+        player.towns.apply_unchecked(&mut self.player.towns);
+        player.settlements.apply_unchecked(&mut self.player.settlements);
+        player.hand.apply_unchecked(&mut self.player.hand);
+        player.profile.apply_unchecked(&mut self.player.profile);
+        road.owner.apply_unchecked(&mut self.road.owner);
+        settle_place.occupancy.apply_unchecked(&mut self.settle_place.occupancy);
+        if let Some(robber) = robber {
+            self.robber = robber;
+        }
+        Ok(())
+    }
+}
+
+impl StateDelta {
+    /// Check that every one of the relation deltas fits the state (see
+    /// [`RelationDelta::validate`]), that the board topology and the players
+    /// are left as is, and that the changes refer only to the entities of
+    /// the state
+    pub fn validate(&self, state: &GameState) -> Result<(), DeltaError> {
+        use DeltaError::*;
+
+        self.player.placed_roads.validate(&state.player.placed_roads)?;
+        self.player.towns.validate(&state.player.towns)?;
+        self.player.settlements.validate(&state.player.settlements)?;
+        self.player.hand.validate(&state.player.hand)?;
+        self.player.profile.validate(&state.player.profile)?;
+        self.road.owner.validate(&state.road.owner)?;
+        self.settle_place.occupancy.validate(&state.settle_place.occupancy)?;
+
+        let players = state.player_count as usize;
+        for (delta, relation) in [
+            (self.road.owner.len, state.road.owner.len()),
+            (self.settle_place.occupancy.len, state.settle_place.occupancy.len()),
+            (self.player.placed_roads.len, players),
+            (self.player.towns.len, players),
+            (self.player.settlements.len, players),
+            (self.player.hand.len, players),
+            (self.player.profile.len, players),
+        ] {
+            if delta != relation {
+                return Err(FixedLength {
+                    expected: relation,
+                    actual: delta,
+                });
+            }
+        }
+
+        let player = |player: PlayerID| match player.get() < state.player_count {
+            true => Ok(()),
+            false => Err(UnknownPlayer(player)),
+        };
+        let road = |road: RoadID| match usize::from(road) < state.road.settle_places.len() {
+            true => Ok(()),
+            false => Err(UnknownRoad(road)),
+        };
+        let settle_place = |settle_place: SettlePlaceID| {
+            match usize::from(settle_place) < state.settle_place.roads.len() {
+                true => Ok(()),
+                false => Err(UnknownSettlePlace(settle_place)),
+            }
+        };
+
+        for (_, owner) in &self.road.owner.changed {
+            owner.map_or(Ok(()), player)?;
+        }
+        for (_, occupancy) in &self.settle_place.occupancy.changed {
+            match occupancy {
+                SettlePlace::Settlement(owner) | SettlePlace::Town(owner) => player(*owner)?,
+                SettlePlace::Empty => {}
+            }
+        }
+        for (_, roads) in &self.player.placed_roads.changed {
+            roads.iter().copied().try_for_each(road)?;
+        }
+        let pieces = self.player.towns.changed.iter().chain(&self.player.settlements.changed);
+        for (_, settle_places) in pieces {
+            settle_places.iter().copied().try_for_each(settle_place)?;
+        }
+        if let Some(Some(tile)) = self.robber {
+            if usize::from(tile) >= state.tile.position.len() {
+                return Err(UnknownTile(tile));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether applying the delta to the state would change nothing
+    pub fn is_empty(&self, state: &GameState) -> bool {
+        self.player.placed_roads.is_empty(&state.player.placed_roads)
+            && self.player.towns.is_empty(&state.player.towns)
+            && self.player.settlements.is_empty(&state.player.settlements)
+            && self.player.hand.is_empty(&state.player.hand)
+            && self.player.profile.is_empty(&state.player.profile)
+            && self.road.owner.is_empty(&state.road.owner)
+            && self.settle_place.occupancy.is_empty(&state.settle_place.occupancy)
+            && self.robber.is_none_or(|robber| robber == state.robber)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use enum_map::enum_map;
+
+    use crate::{
+        decode_config,
+        ids::{PlayerID, RoadID, SettlePlaceID, TileID},
+        relations::GameState,
+        types::{PlayerHand, Resource, SettlePlace},
+        MapConfig,
+    };
+
+    use super::{DeltaError, StateDelta};
+
+    fn decode() -> GameState {
+        let config = MapConfig::standard();
+        decode_config(config, 2).unwrap()
+    }
+
+    fn hand(wheat: u8) -> PlayerHand {
+        PlayerHand {
+            resources: enum_map! { Resource::Wheat => wheat, _ => 0 },
+            settlements: 5,
+            towns: 4,
+            roads: 15,
+        }
+    }
+
+    #[test]
+    fn snapshot_plus_deltas_equals_replayed_state() {
+        // The state the actions are "replayed" upon on the server
+        let mut current = decode();
+        // The last snapshot broadcasted, which the server keeps to diff against
+        let mut previous = decode();
+        // The client, which only ever receives deltas
+        let mut client = decode();
+
+        let steps: [&dyn Fn(&mut GameState); 7] = [
+            &|state| {
+                state.player.hand[PlayerID(0)] = hand(1);
+            },
+            &|state| {
+                state.player.settlements[PlayerID(0)].push(SettlePlaceID(3));
+                state.settle_place.occupancy[SettlePlaceID(3)] =
+                    SettlePlace::Settlement(PlayerID(0));
+                state.player.placed_roads[PlayerID(0)].push(RoadID(4));
+                state.road.owner[RoadID(4)] = Some(PlayerID(0));
+            },
+            &|state| {
+                state.player.hand[PlayerID(1)] = hand(2);
+            },
+            &|state| {
+                let town = state.player.settlements[PlayerID(0)].remove(0);
+                state.player.towns[PlayerID(0)].push(town);
+                state.settle_place.occupancy[town] = SettlePlace::Town(PlayerID(0));
+                state.player.hand[PlayerID(1)] = hand(0);
+            },
+            &|state| {
+                state.player.profile[PlayerID(1)].name = "Bob".to_owned();
+            },
+            &|state| {
+                state.robber = Some(TileID(4));
+            },
+            &|state| {
+                state.robber = None;
+            },
+        ];
+
+        for step in steps {
+            step(&mut current);
+            let delta = previous.diff(&current);
+            assert!(!delta.is_empty(&previous));
+
+            // Deltas go over the wire
+            let delta: StateDelta =
+                serde_json::from_str(&serde_json::to_string(&delta).unwrap()).unwrap();
+
+            previous.apply_delta(delta.clone()).unwrap();
+            client.apply_delta(delta).unwrap();
+            assert_eq!(client, current);
+        }
+
+        assert!(previous.diff(&current).is_empty(&previous));
+    }
+
+    #[test]
+    fn delta_only_contains_changes() {
+        let mut old = decode();
+        let mut new = decode();
+        new.player.hand[PlayerID(1)] = hand(1);
+
+        let delta = old.diff(&new);
+        assert_eq!(delta.player.hand.len, 2);
+        assert_eq!(delta.player.hand.changed, vec![(PlayerID(1), hand(1))]);
+
+        assert_eq!(delta.robber, None);
+        old.apply_delta(delta).unwrap();
+        assert_eq!(old, new);
+    }
+
+    #[test]
+    fn malformed_deltas_are_rejected() {
+        let state = decode();
+        let mut moved = state.clone();
+        moved.road.owner[RoadID(2)] = Some(PlayerID(0));
+        moved.road.owner[RoadID(5)] = Some(PlayerID(1));
+        let delta = state.diff(&moved);
+
+        let mut unsorted = delta.clone();
+        unsorted.road.owner.changed.reverse();
+        let mut past_the_end = delta.clone();
+        past_the_end.road.owner.len += 1;
+        past_the_end.road.owner.changed.push((RoadID(1000), None));
+        let mut short = delta.clone();
+        short.player.hand.len += 1;
+        let mut duplicate = delta.clone();
+        duplicate.road.owner.changed.push((RoadID(5), None));
+
+        let mut shrunk = delta.clone();
+        shrunk.road.owner.len = 0;
+        shrunk.road.owner.changed.clear();
+        let mut kicked = delta.clone();
+        kicked.player.towns.len = 1;
+        let mut joined = delta.clone();
+        joined.player.hand.len = 3;
+        joined.player.hand.changed.push((PlayerID(2), hand(0)));
+        let mut unknown_player = delta.clone();
+        unknown_player.road.owner.changed[0].1 = Some(PlayerID(7));
+        let mut unknown_settle_place = delta.clone();
+        unknown_settle_place.player.towns.changed.push((PlayerID(1), vec![SettlePlaceID(54)]));
+        let mut unknown_tile = delta.clone();
+        unknown_tile.robber = Some(Some(TileID(19)));
+
+        for (delta, error) in [
+            (unsorted, DeltaError::UnsortedKey(2)),
+            (past_the_end, DeltaError::KeyOutOfRange { key: 1000, len: 72 }),
+            (short, DeltaError::LengthMismatch { expected: 3, actual: 2 }),
+            (duplicate, DeltaError::UnsortedKey(5)),
+            (shrunk, DeltaError::FixedLength { expected: 72, actual: 0 }),
+            (kicked, DeltaError::FixedLength { expected: 2, actual: 1 }),
+            (joined, DeltaError::FixedLength { expected: 2, actual: 3 }),
+            (unknown_player, DeltaError::UnknownPlayer(PlayerID(7))),
+            (unknown_settle_place, DeltaError::UnknownSettlePlace(SettlePlaceID(54))),
+            (unknown_tile, DeltaError::UnknownTile(TileID(19))),
+        ] {
+            let mut target = state.clone();
+            assert_eq!(target.apply_delta(delta), Err(error));
+            assert_eq!(target, state);
+        }
+
+        let mut target = state.clone();
+        target.apply_delta(delta).unwrap();
+        assert_eq!(target, moved);
+    }
+}
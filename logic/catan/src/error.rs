@@ -0,0 +1,114 @@
+//! Errors of the crate in one place. Each of the operations returns its own
+//! specific error, and [`Error`] unifies them, for the callers which only
+//! bubble the errors up.
+
+pub use crate::{
+    config::{LoadConfigError, MapConfigError},
+    delta::DeltaError,
+    features::FeaturesError,
+    pregame::LobbyError,
+    save::SaveError,
+    topology::TopologyDiagnostics,
+    transactions::TransactionError,
+    DecodeConfigError,
+};
+
+/// Any error produced by the crate. The specific error is available as the
+/// [`std::error::Error::source`] of it.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("could not load the map")]
+    Load(#[from] LoadConfigError),
+    #[error("map config is invalid")]
+    Validation(#[from] MapConfigError),
+    #[error("could not decode the map")]
+    Decode(#[from] DecodeConfigError),
+    #[error("board is not consistent")]
+    Topology(#[from] TopologyDiagnostics),
+    #[error("transaction was rejected")]
+    Transaction(#[from] TransactionError),
+    #[error("lobby change was rejected")]
+    Lobby(#[from] LobbyError),
+    #[error("changes do not fit the state")]
+    Delta(#[from] DeltaError),
+    #[error("could not extract the features")]
+    Features(#[from] FeaturesError),
+    #[error("could not save or load the game")]
+    Save(#[from] SaveError),
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error as _;
+
+    use crate::{
+        config::load_json,
+        decode_config,
+        ids::RoadID,
+        topology::{verify_topology, TopologyViolation},
+        types::TileTerrain,
+        GameState, MapConfig, MapConfigBuilder,
+    };
+
+    use super::{DecodeConfigError, Error, LoadConfigError, MapConfigError, TopologyDiagnostics};
+
+    fn chain(err: &(dyn std::error::Error + 'static)) -> Vec<String> {
+        std::iter::successors(Some(err), |err| (*err).source())
+            .map(|err| err.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn errors_chain_down_to_the_cause() {
+        let config = MapConfig::standard();
+        let mut json = serde_json::to_value(&config).unwrap();
+        json["tilePlacement"][1] = json["tilePlacement"][0].clone();
+        let err = Error::from(load_json(&json.to_string()).unwrap_err());
+        assert!(matches!(
+            err,
+            Error::Load(LoadConfigError::Invalid(MapConfigError::DuplicateTile(_)))
+        ));
+        assert_eq!(
+            chain(&err),
+            [
+                "could not load the map",
+                "map config is invalid",
+                "more than one tile is placed at [2, 1]",
+            ]
+        );
+
+        let err = Error::from(load_json("{").unwrap_err());
+        assert_eq!(chain(&err).len(), 3);
+
+        let err = Error::from(decode_config(config.clone(), 0).unwrap_err());
+        assert!(matches!(err, Error::Decode(DecodeConfigError::InvalidPlayerCount(0))));
+        assert_eq!(chain(&err)[1], "0 players can't play the game");
+        assert!(err.source().unwrap().source().is_none());
+
+        fn build() -> Result<MapConfig, Error> {
+            Ok(MapConfigBuilder::new()
+                .add_tile([0, 0], TileTerrain::Forest)
+                .add_tile([0, 0], TileTerrain::Field)
+                .build()?)
+        }
+        let err = build().unwrap_err();
+        assert!(matches!(err, Error::Validation(MapConfigError::DuplicateTile([0, 0]))));
+        assert_eq!(chain(&err).len(), 2);
+
+        fn verify(state: &GameState) -> Result<(), Error> {
+            Ok(verify_topology(state)?)
+        }
+        let mut state = decode_config(config, 2).unwrap();
+        state.road.settle_places[RoadID(0)][0] = state.road.settle_places[RoadID(0)][1];
+        let err = verify(&state).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Topology(TopologyDiagnostics {
+                violation: TopologyViolation::DegenerateRoad(road),
+                ..
+            }) if road == RoadID(0)
+        ));
+        assert_eq!(chain(&err).len(), 2);
+    }
+}
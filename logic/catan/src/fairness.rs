@@ -0,0 +1,165 @@
+use crate::{
+    bitset::BitSet,
+    ids::{PlayerInt, SettlePlaceID},
+    relations::GameState,
+};
+
+/// How evenly the board favors each of the seats, judged by the settle
+/// places they would get during the initial placement.
+///
+/// Seats take turns in the snake order of the initial placement (first to
+/// last, then last to first), each taking the free settle place with the
+/// most pips, not counting the robbed tile (see
+/// [`crate::Board::production_value`]). Ties are broken in favor of the
+/// settle place which blocks the fewest pips of the free settle places next
+/// to it, and then by the lowest ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fairness {
+    /// Total pips of the settle places taken by each of the seats, in turn
+    /// order
+    pub seat_pips: Vec<u32>,
+    /// Settle places taken by each of the seats, in the order they were taken
+    pub picks: Vec<Vec<SettlePlaceID>>,
+}
+
+/// Amount of the settle places each seat takes during the initial placement
+pub const INITIAL_SETTLEMENTS: usize = 2;
+
+impl Fairness {
+    /// Difference between the pips of the most and the least favored seats.
+    /// Zero for the perfectly fair board.
+    pub fn spread(&self) -> u32 {
+        let max = self.seat_pips.iter().max().copied().unwrap_or(0);
+        let min = self.seat_pips.iter().min().copied().unwrap_or(0);
+        max - min
+    }
+
+    /// Pips of the least favored seat relative to the most favored one,
+    /// from 0 to 1. One for the perfectly fair board.
+    pub fn score(&self) -> f64 {
+        let max = self.seat_pips.iter().max().copied().unwrap_or(0);
+        let min = self.seat_pips.iter().min().copied().unwrap_or(0);
+        match max {
+            0 => 1.,
+            max => min as f64 / max as f64,
+        }
+    }
+}
+
+impl GameState {
+    /// Score the fairness of the board for the given amount of seats, before
+    /// any of the pieces are placed. See [`Fairness`].
+    pub fn fairness(&self, seats: PlayerInt) -> Fairness {
+        let board = self.board();
+        let values = self.production_values();
+        let pips = |settle_place: SettlePlaceID| u32::from(values[settle_place]);
+        let seats = usize::from(seats);
+        let mut free = BitSet::<SettlePlaceID>::full(board.settle_places().len());
+        let mut picks = vec![Vec::with_capacity(INITIAL_SETTLEMENTS); seats];
+
+        let snake = (0..INITIAL_SETTLEMENTS).flat_map(|round| {
+            (0..seats).map(move |seat| if round % 2 == 0 { seat } else { seats - 1 - seat })
+        });
+        for seat in snake {
+            let blocked_pips = |settle_place: SettlePlaceID| -> u32 {
+                board
+                    .adjacent_settle_places(settle_place)
+                    .filter(|adjacent| free.contains(*adjacent))
+                    .map(pips)
+                    .sum()
+            };
+            let best = free.iter().max_by_key(|settle_place| {
+                (
+                    pips(*settle_place),
+                    std::cmp::Reverse(blocked_pips(*settle_place)),
+                    std::cmp::Reverse(usize::from(*settle_place)),
+                )
+            });
+            let Some(best) = best else {
+                break;
+            };
+            // Nobody may settle next to the taken settle place
+            free.remove(best);
+            for adjacent in board.adjacent_settle_places(best) {
+                free.remove(adjacent);
+            }
+            picks[seat].push(best);
+        }
+
+        Fairness {
+            seat_pips: picks
+                .iter()
+                .map(|taken| taken.iter().map(|settle_place| pips(*settle_place)).sum())
+                .collect(),
+            picks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        types::{DiceMarker, TileTerrain},
+        MapConfig, MapConfigBuilder,
+    };
+
+    #[test]
+    fn pips_count_the_outcomes_of_the_roll() {
+        assert_eq!(DiceMarker::ALL.map(DiceMarker::pips), [1, 2, 3, 4, 5, 5, 4, 3, 2, 1]);
+        let total: u8 = DiceMarker::ALL.into_iter().map(DiceMarker::pips).sum();
+        // Every outcome of the two dice, except for the six sevens
+        assert_eq!(total, 36 - 6);
+    }
+
+    #[test]
+    fn seats_take_the_best_free_settle_places() {
+        let state = decode_config(MapConfig::standard(), 4).unwrap();
+        let board = state.board();
+        let fairness = state.fairness(4);
+        assert_eq!(fairness.picks.len(), 4);
+
+        let picks: Vec<_> = fairness.picks.iter().flatten().collect();
+        assert_eq!(picks.len(), 8);
+        for (idx, settle_place) in picks.iter().enumerate() {
+            for other in &picks[idx + 1..] {
+                assert_ne!(settle_place, other);
+                assert!(board
+                    .adjacent_settle_places(**settle_place)
+                    .all(|adjacent| adjacent != **other));
+            }
+        }
+        // The first seat gets the best settle place of the whole board
+        let best = board.settle_places().map(|settle_place| board.pips(settle_place)).max();
+        assert_eq!(Some(board.pips(fairness.picks[0][0])), best);
+        assert!(fairness.score() > 0. && fairness.score() <= 1.);
+        assert_eq!(
+            fairness.spread(),
+            fairness.seat_pips.iter().max().unwrap() - fairness.seat_pips.iter().min().unwrap()
+        );
+    }
+
+    #[test]
+    fn fairness_does_not_depend_on_the_orientation() {
+        let config = MapConfig::standard();
+        let fairness = decode_config(config.clone(), 3).unwrap().fairness(3);
+        for steps in 1..6 {
+            let rotated = config.rotated(steps).unwrap();
+            let state = decode_config(rotated.clone(), 3).unwrap();
+            assert_eq!(state.fairness(3).seat_pips, fairness.seat_pips, "{steps}");
+            let state = decode_config(rotated.mirrored().unwrap(), 3).unwrap();
+            assert_eq!(state.fairness(3).seat_pips, fairness.seat_pips, "{steps}");
+        }
+    }
+
+    #[test]
+    fn boards_without_room_are_unfair() {
+        let config = MapConfigBuilder::new().add_tile([0, 0], TileTerrain::Field).build().unwrap();
+        let fairness = decode_config(config, 4).unwrap().fairness(4);
+        // A lone tile fits only three settlements
+        assert_eq!(fairness.picks.iter().flatten().count(), 3);
+        assert_eq!(fairness.seat_pips, [4, 4, 4, 0]);
+        assert_eq!(fairness.spread(), 4);
+        assert_eq!(fairness.score(), 0.);
+    }
+}
@@ -0,0 +1,212 @@
+use enum_map::Enum;
+
+use crate::{
+    ids::PlayerID,
+    relations::GameState,
+    types::{Resource, TileTerrain},
+};
+
+/// Numeric encoding of the game state, for the machine learning models.
+///
+/// Every plane is a row-major matrix flattened into the Vec, with its shape
+/// documented on the field. For the given map and player count, the shapes
+/// never change throughout the game. Players are ordered from the
+/// perspective of the player the features were extracted for: that player
+/// always comes first, followed by the rest in turn order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Features {
+    /// `[tiles, terrains]`: one-hot encoding of the terrain of each tile
+    pub terrain: Vec<f32>,
+    /// `[settle places]`: total pips of the markers around each settle
+    /// place, see [`crate::Board::pips`]
+    pub pips: Vec<f32>,
+    /// `[tiles]`: 1 on the tile the robber is on, all 0 while it is off
+    /// the board
+    pub robber: Vec<f32>,
+    /// `[players, settle places]`: 1 where the player has a settlement
+    pub settlements: Vec<f32>,
+    /// `[players, settle places]`: 1 where the player has a town
+    pub towns: Vec<f32>,
+    /// `[players, roads]`: 1 where the player has placed a road
+    pub roads: Vec<f32>,
+    /// `[players, HAND_FEATURES]`: amount of each resource in the hand,
+    /// followed by the amount of settlements, towns and roads left to place
+    pub hands: Vec<f32>,
+}
+
+/// Amount of features describing a single hand in [`Features::hands`]
+pub const HAND_FEATURES: usize = Resource::LENGTH + 3;
+
+/// Reasons the features could not be extracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum FeaturesError {
+    /// The player the features are extracted for is not in the game
+    #[error("player {0:?} is not in the game")]
+    UnknownPlayer(PlayerID),
+}
+
+impl Features {
+    /// Extract the features of the state from the perspective of the player
+    pub fn extract(state: &GameState, perspective: PlayerID) -> Result<Self, FeaturesError> {
+        let tile_count = state.tile.resource.len();
+        let settle_place_count = state.settle_place.roads.len();
+        let road_count = state.road.settle_places.len();
+        let player_count = state.player_count as usize;
+        if usize::from(perspective) >= player_count {
+            return Err(FeaturesError::UnknownPlayer(perspective));
+        }
+        let players = (0..player_count).map(|offset| {
+            PlayerID::try_from((usize::from(perspective) + offset) % player_count).unwrap()
+        });
+
+        let mut terrain = vec![0.; tile_count * TileTerrain::ALL.len()];
+        for (tile, resource) in &state.tile.resource {
+            let column = TileTerrain::ALL.iter().position(|t| t == resource).unwrap();
+            terrain[usize::from(tile) * TileTerrain::ALL.len() + column] = 1.;
+        }
+
+        let board = state.board();
+        let pips = board
+            .settle_places()
+            .map(|settle_place| board.pips(settle_place) as f32)
+            .collect();
+        let mut robber = vec![0.; tile_count];
+        if let Some(tile) = board.robber() {
+            robber[usize::from(tile)] = 1.;
+        }
+
+        let mut settlements = vec![0.; player_count * settle_place_count];
+        let mut towns = vec![0.; player_count * settle_place_count];
+        let mut roads = vec![0.; player_count * road_count];
+        let mut hands = Vec::with_capacity(player_count * HAND_FEATURES);
+        for (row, player) in players.enumerate() {
+            for settle_place in &state.player.settlements[player] {
+                settlements[row * settle_place_count + usize::from(*settle_place)] = 1.;
+            }
+            for settle_place in &state.player.towns[player] {
+                towns[row * settle_place_count + usize::from(*settle_place)] = 1.;
+            }
+            for road in &state.player.placed_roads[player] {
+                roads[row * road_count + usize::from(*road)] = 1.;
+            }
+
+            let hand = &state.player.hand[player];
+            hands.extend(hand.resources.values().map(|amount| *amount as f32));
+            hands.extend([hand.settlements, hand.towns, hand.roads].map(f32::from));
+        }
+
+        Ok(Self {
+            terrain,
+            pips,
+            robber,
+            settlements,
+            towns,
+            roads,
+            hands,
+        })
+    }
+
+    /// Concatenate all of the planes into a single vector, in the order
+    /// of their declaration
+    pub fn to_vec(&self) -> Vec<f32> {
+        [
+            &self.terrain[..],
+            &self.pips,
+            &self.robber,
+            &self.settlements,
+            &self.towns,
+            &self.roads,
+            &self.hands,
+        ]
+        .concat()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use enum_map::enum_map;
+
+    use crate::{
+        decode_config,
+        ids::{PlayerID, RoadID, SettlePlaceID},
+        relations::GameState,
+        types::Resource,
+        MapConfig,
+    };
+
+    use super::{Features, FeaturesError, HAND_FEATURES};
+
+    fn two_player_game() -> GameState {
+        let config = MapConfig::standard();
+        let mut state = decode_config(config, 2).unwrap();
+        state.player.hand[PlayerID(0)].resources = enum_map! { Resource::Ore => 3, _ => 0 };
+        state.player.settlements[PlayerID(1)].push(SettlePlaceID(5));
+        state.player.placed_roads[PlayerID(1)].push(RoadID(7));
+        state
+    }
+
+    #[test]
+    fn shapes_are_fixed_by_the_map() {
+        let state = two_player_game();
+        let features = Features::extract(&state, PlayerID(0)).unwrap();
+
+        assert_eq!(features.terrain.len(), 19 * 6);
+        assert_eq!(features.pips.len(), 54);
+        assert_eq!(features.robber.len(), 19);
+        assert_eq!(features.settlements.len(), 2 * 54);
+        assert_eq!(features.towns.len(), 2 * 54);
+        assert_eq!(features.roads.len(), 2 * 72);
+        assert_eq!(features.hands.len(), 2 * HAND_FEATURES);
+        assert_eq!(features.terrain.iter().sum::<f32>(), 19.);
+        assert_eq!(
+            features.to_vec().len(),
+            19 * 6 + 54 + 19 + 2 * 54 + 2 * 54 + 2 * 72 + 2 * HAND_FEATURES
+        );
+    }
+
+    #[test]
+    fn perspective_player_comes_first() {
+        let state = two_player_game();
+
+        let first = Features::extract(&state, PlayerID(0)).unwrap();
+        assert_eq!(first.settlements[54 + 5], 1.);
+        assert_eq!(first.roads[72 + 7], 1.);
+        assert_eq!(first.hands[Resource::Ore as usize], 3.);
+
+        let second = Features::extract(&state, PlayerID(1)).unwrap();
+        assert_eq!(second.settlements[5], 1.);
+        assert_eq!(second.roads[7], 1.);
+        assert_eq!(second.hands[HAND_FEATURES + Resource::Ore as usize], 3.);
+        assert_eq!(second.terrain, first.terrain);
+
+        assert_eq!(
+            Features::extract(&state, PlayerID(2)),
+            Err(FeaturesError::UnknownPlayer(PlayerID(2)))
+        );
+    }
+
+    #[test]
+    fn board_planes_follow_the_markers_and_the_robber() {
+        let mut state = two_player_game();
+        let features = Features::extract(&state, PlayerID(0)).unwrap();
+        let board = state.board();
+        for settle_place in board.settle_places() {
+            let pips = features.pips[usize::from(settle_place)];
+            assert_eq!(pips, board.pips(settle_place) as f32);
+        }
+        // Every marker is counted once per each of the six vertices of its tile
+        let markers: u32 = board
+            .dice_markers()
+            .map(|marker| u32::from(board.marker_value(marker).pips()))
+            .sum();
+        assert_eq!(features.pips.iter().sum::<f32>(), (6 * markers) as f32);
+
+        let desert = board.robber().unwrap();
+        assert_eq!(features.robber.iter().sum::<f32>(), 1.);
+        assert_eq!(features.robber[usize::from(desert)], 1.);
+        state.robber = None;
+        let features = Features::extract(&state, PlayerID(0)).unwrap();
+        assert!(features.robber.iter().all(|robber| *robber == 0.));
+    }
+}
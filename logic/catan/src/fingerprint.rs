@@ -0,0 +1,187 @@
+use std::hash::{Hash, Hasher};
+
+use crate::{relations::GameState, MapConfig};
+
+/// 64-bit FNV-1a hasher, which produces the same hashes on every platform
+/// for the same toolchain.
+///
+/// Unlike the standard hashers, integers are always hashed as little-endian,
+/// and `usize`/`isize` (which are used for lengths and enum discriminants)
+/// are always hashed as 64-bit, so that a 32-bit client (e.g. wasm32) gets
+/// the same hash as the 64-bit server. The bytes fed to the hasher still come
+/// from the `Hash` impls of std (e.g. the length prefixes of slices), which are
+/// not guaranteed to stay the same between compiler versions.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64)
+    }
+}
+
+/// Hash the value with the [`StableHasher`]
+pub fn fingerprint<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = StableHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl MapConfig {
+    /// Stable 64-bit hash of the config. Peers loading the same map get the
+    /// same fingerprint, regardless of their platform.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(self)
+    }
+}
+
+impl GameState {
+    /// Stable 64-bit hash of the whole state. Peers comparing fingerprints
+    /// after every turn can detect that their states diverged.
+    ///
+    /// Fingerprints are only comparable between the same versions of the
+    /// crate built with the same toolchain, since any change to the game state
+    /// (or to the way std hashes it) changes them as well.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::Hasher;
+
+    use crate::{
+        decode_config,
+        ids::{PlayerID, RoadID},
+        MapConfig,
+    };
+
+    use super::StableHasher;
+
+    #[test]
+    fn matches_reference_fnv() {
+        assert_eq!(StableHasher::new().finish(), 0xcbf29ce484222325);
+
+        let mut hasher = StableHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+
+        let mut hasher = StableHasher::new();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.finish(), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn integers_are_hashed_independently_of_platform() {
+        let mut usize_hasher = StableHasher::new();
+        usize_hasher.write_usize(0x0102);
+        let mut u64_hasher = StableHasher::new();
+        u64_hasher.write(&[0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(usize_hasher.finish(), u64_hasher.finish());
+    }
+
+    #[test]
+    fn equal_states_have_equal_fingerprints() {
+        assert_eq!(MapConfig::standard().fingerprint(), MapConfig::standard().fingerprint());
+
+        let mut state = decode_config(MapConfig::standard(), 2).unwrap();
+        let other = decode_config(MapConfig::standard(), 2).unwrap();
+        assert_eq!(state.fingerprint(), other.fingerprint());
+
+        state.player.hand[PlayerID(0)].roads -= 1;
+        assert_ne!(state.fingerprint(), other.fingerprint());
+        let diverged = state.fingerprint();
+        state.road.owner[RoadID(0)] = Some(PlayerID(0));
+        assert_ne!(state.fingerprint(), diverged);
+    }
+
+    /// The decoding and the shuffling must produce exactly the same results on
+    /// every platform, for the peers in lockstep to agree. These constants may
+    /// only change together with the layout of the decoded state. Wide IDs are
+    /// hashed with their width, so they have fingerprints of their own.
+    #[test]
+    #[cfg(not(feature = "wide-ids"))]
+    fn decoding_is_identical_across_platforms() {
+        use super::fingerprint;
+        use crate::rules::GameRules;
+
+        let state = decode_config(MapConfig::standard(), 4).unwrap();
+        let topology = (
+            &state.tile,
+            &state.road.settle_places,
+            &state.settle_place.roads,
+            &state.settle_place.tiles,
+        );
+        assert_eq!(fingerprint(&topology), 0x492d_abef_e73f_d85d);
+        assert_eq!(state.fingerprint(), 0x144d_9089_93da_40d5);
+
+        let rules = GameRules {
+            shuffle_seed: Some(0x5eed),
+            ..Default::default()
+        };
+        assert_eq!(fingerprint(&rules.development_deck()), 0xc084_4ad8_14c7_0678);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    derive_resource_tiles,
+    ids::{PlayerInt, TileID},
+    relations::GameState,
+    rng::Rng,
+    rules::GameRules,
+    topology::BoardTopology,
+    types::{DiceMarker, Harbour, TileTerrain},
+    DecodeConfigError, MapConfig, MapConfigError, ResourceTileDerivation,
+};
+
+/// Randomized layout of the board: which terrains, dice markers and harbours
+/// are where. Everything else about the board is fixed by the map config.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BoardAssignment {
+    /// Terrain of each of the tiles, indexed by TileID
+    pub terrains: Vec<TileTerrain>,
+    /// Dice marker laid on each of the tiles, indexed by TileID. Deserts have
+    /// none.
+    pub dice_markers: Vec<Option<DiceMarker>>,
+    /// Kind of each of the harbours, indexed by the harbour ID
+    pub harbours: Vec<Harbour>,
+}
+
+impl BoardAssignment {
+    /// The config with the assignment as its default distribution
+    pub fn to_config(&self, config: &MapConfig) -> MapConfig {
+        MapConfig {
+            default_tiles: self.terrains.clone(),
+            default_dice_markers: self.dice_markers.clone(),
+            default_harbours: self.harbours.clone(),
+            robber_tile: config.robber_tile.filter(|tile| self.is_desert(*tile)),
+            ..config.clone()
+        }
+    }
+
+    /// Lay the assignment out on the state decoded from the same config. The
+    /// robber, unless it is off the board, is moved to the desert it would
+    /// start on if the state was decoded from [`BoardAssignment::to_config`].
+    pub fn apply(&self, config: &MapConfig, state: &mut GameState) {
+        for (terrain, assigned) in state.tile.resource.values_mut().zip(&self.terrains) {
+            *terrain = *assigned;
+        }
+        for (marker, assigned) in state.tile.dice_marker.values_mut().zip(&self.dice_markers) {
+            *marker = *assigned;
+        }
+        let ResourceTileDerivation {
+            tile_resource_tiles,
+            resource_tiles,
+            dice_markers,
+        } = derive_resource_tiles(&state.tile.resource, &state.tile.dice_marker);
+        state.tile.resource_tile = tile_resource_tiles;
+        state.resource_tile = resource_tiles;
+        state.dice_marker = dice_markers;
+        state.harbour.kind.clone_from(&self.harbours);
+        if state.robber.is_some() {
+            state.robber = self.to_config(config).robber_tile();
+        }
+    }
+
+    fn is_desert(&self, tile: TileID) -> bool {
+        self.terrains.get(usize::from(tile)) == Some(&TileTerrain::Desert)
+    }
+}
+
+/// Shuffles producing the assignments of the single map config
+struct Generator {
+    rng: Rng,
+    /// Tiles which are not fixed to their terrain
+    shuffled_tiles: Vec<usize>,
+    base: BoardAssignment,
+}
+
+impl Generator {
+    fn new(config: &MapConfig, seed: u64) -> Result<Self, MapConfigError> {
+        config.validate()?;
+        let mut fixed = vec![false; config.tile_placement.len()];
+        for terrain in TileTerrain::ALL {
+            for tile in &config.fixed_tiles[terrain] {
+                fixed[usize::from(*tile)] = true;
+            }
+        }
+        Ok(Self {
+            rng: Rng::from_seed(seed),
+            shuffled_tiles: (0..fixed.len()).filter(|idx| !fixed[*idx]).collect(),
+            base: BoardAssignment {
+                terrains: config.default_tiles.clone(),
+                dice_markers: config.dice_markers(),
+                harbours: config.default_harbours.clone(),
+            },
+        })
+    }
+
+    /// Shuffle the terrains of the tiles which are not fixed, then the dice
+    /// markers among the resource tiles, then the harbours
+    fn next_assignment(&mut self) -> BoardAssignment {
+        let mut terrains = self.base.terrains.clone();
+        let mut pool: Vec<_> = self.shuffled_tiles.iter().map(|idx| terrains[*idx]).collect();
+        self.rng.shuffle(&mut pool);
+        for (idx, terrain) in self.shuffled_tiles.iter().zip(pool) {
+            terrains[*idx] = terrain;
+        }
+
+        // Fixed tiles match their default terrain, so the amount of the
+        // resource tiles is always the same as the amount of the markers
+        let mut markers: Vec<_> = self.base.dice_markers.iter().flatten().copied().collect();
+        self.rng.shuffle(&mut markers);
+        let mut markers = markers.into_iter();
+        let dice_markers = terrains
+            .iter()
+            .map(|terrain| match terrain {
+                TileTerrain::Desert => None,
+                _ => markers.next(),
+            })
+            .collect();
+
+        let mut harbours = self.base.harbours.clone();
+        self.rng.shuffle(&mut harbours);
+
+        BoardAssignment {
+            terrains,
+            dice_markers,
+            harbours,
+        }
+    }
+}
+
+/// Generate `n` random layouts of the map. The same config and seed always
+/// produce the same layouts, on every platform.
+pub fn generate_boards(
+    config: &MapConfig,
+    n: usize,
+    seed: u64,
+) -> Result<impl Iterator<Item = BoardAssignment>, MapConfigError> {
+    let mut generator = Generator::new(config, seed)?;
+    Ok((0..n).map(move |_| generator.next_assignment()))
+}
+
+/// Generate `n` games on random layouts of the map, the same ones as
+/// [`generate_boards`] produces for the seed.
+///
+/// The map is decoded only once into the [`BoardTopology`], and every game
+/// is laid out on the instance of it, which is much cheaper than decoding
+/// each of them.
+pub fn generate_states<'a>(
+    config: &'a MapConfig,
+    player_count: PlayerInt,
+    rules: &GameRules,
+    n: usize,
+    seed: u64,
+) -> Result<impl Iterator<Item = GameState> + 'a, DecodeConfigError> {
+    let base = BoardTopology::new(config.clone())?.instantiate(player_count, rules)?;
+    let assignments =
+        generate_boards(config, n, seed).map_err(DecodeConfigError::InvalidConfig)?;
+    Ok(assignments.map(move |assignment| {
+        let mut state = base.clone();
+        assignment.apply(config, &mut state);
+        state
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config, decode_config_with_rules,
+        ids::{TileID, TileInt},
+        rules::{GameRules, RobberStart},
+        types::{DiceMarker, TileTerrain},
+        MapConfig, MapConfigBuilder,
+    };
+
+    use super::{generate_boards, generate_states};
+
+    #[test]
+    fn boards_are_shuffled_deterministically() {
+        let config = MapConfig::standard();
+        let boards: Vec<_> = generate_boards(&config, 20, 7).unwrap().collect();
+        assert_eq!(boards.len(), 20);
+        assert_eq!(boards, generate_boards(&config, 20, 7).unwrap().collect::<Vec<_>>());
+        assert_ne!(boards, generate_boards(&config, 20, 8).unwrap().collect::<Vec<_>>());
+        assert!(boards.windows(2).all(|pair| pair[0] != pair[1]));
+
+        for board in &boards {
+            let generated = board.to_config(&config);
+            assert_eq!(generated.validate(), Ok(()));
+            let mut terrains = board.terrains.clone();
+            let mut default = config.default_tiles.clone();
+            terrains.sort_by_key(|terrain| format!("{terrain:?}"));
+            default.sort_by_key(|terrain| format!("{terrain:?}"));
+            assert_eq!(terrains, default);
+            let mut markers: Vec<_> = board.dice_markers.iter().flatten().copied().collect();
+            let mut base_game = DiceMarker::BASE_GAME;
+            markers.sort_by_key(|marker| marker.value());
+            base_game.sort_by_key(|marker| marker.value());
+            assert_eq!(markers, base_game);
+        }
+    }
+
+    #[test]
+    fn fixed_tiles_keep_their_terrain() {
+        let config = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .add_tile([1, 0], TileTerrain::Field)
+            .add_fixed_tile([2, 0], TileTerrain::Desert)
+            .add_tile([3, 0], TileTerrain::Mesa)
+            .add_tile([4, 0], TileTerrain::Desert)
+            .set_robber_tile(TileID(4))
+            .build()
+            .unwrap();
+        for board in generate_boards(&config, 50, 1).unwrap() {
+            assert_eq!(board.terrains[2], TileTerrain::Desert);
+            assert_eq!(board.dice_markers[2], None);
+            let generated = board.to_config(&config);
+            // The robber stays on its desert, unless the desert was shuffled
+            // away, in which case it starts on the first one
+            let first_desert = board.terrains.iter().position(|t| *t == TileTerrain::Desert);
+            let robber = match board.terrains[4] {
+                TileTerrain::Desert => TileID(4),
+                _ => TileID(first_desert.unwrap() as TileInt),
+            };
+            assert_eq!(generated.robber_tile(), Some(robber));
+        }
+    }
+
+    #[test]
+    fn generated_states_match_decoded_ones() {
+        let config = MapConfig::standard();
+        let rules = GameRules::default();
+        let boards = generate_boards(&config, 10, 3).unwrap();
+        let states = generate_states(&config, 3, &rules, 10, 3).unwrap();
+        for (board, state) in boards.zip(states) {
+            let decoded = decode_config(board.to_config(&config), 3).unwrap();
+            assert_eq!(state, decoded);
+        }
+
+        let off_board = GameRules {
+            robber_start: RobberStart::OffBoard,
+            ..Default::default()
+        };
+        let mut states = generate_states(&config, 2, &off_board, 1, 3).unwrap();
+        let board = generate_boards(&config, 1, 3).unwrap().next().unwrap();
+        let decoded = decode_config_with_rules(board.to_config(&config), 2, &off_board).unwrap();
+        assert_eq!(states.next(), Some(decoded));
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops::{Index, IndexMut};
+
+use enum_map::{enum_map, EnumMap};
+
+use crate::{ids::CoordInt, types::HexSide};
+
+/// Rectangular grid of values, addressed by the offset coordinates used in
+/// the MapConfig (rows of hexes, with every odd row shifted to the east).
+///
+/// Indexing panics on the positions outside of the grid, while
+/// [`Grid::get`] returns None for them.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    size: [CoordInt; 2],
+    data: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Grid of the given size, with every cell set to the value
+    pub fn new(size @ [width, height]: [CoordInt; 2], value: T) -> Self {
+        Self {
+            size,
+            data: vec![value; width as usize * height as usize],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Grid of the given size, filled row by row with the values.
+    /// Panics if there are not exactly as many values as there are cells.
+    pub fn from_vec(size @ [width, height]: [CoordInt; 2], data: Vec<T>) -> Self {
+        assert_eq!(data.len(), width as usize * height as usize, "grid size mismatch");
+        Self { size, data }
+    }
+
+    /// Width and height of the grid
+    pub fn size(&self) -> [CoordInt; 2] {
+        self.size
+    }
+
+    pub fn contains(&self, [x, y]: [CoordInt; 2]) -> bool {
+        x < self.size[0] && y < self.size[1]
+    }
+
+    pub fn get(&self, position: [CoordInt; 2]) -> Option<&T> {
+        let idx = self.idx(position)?;
+        Some(&self.data[idx])
+    }
+
+    pub fn get_mut(&mut self, position: [CoordInt; 2]) -> Option<&mut T> {
+        let idx = self.idx(position)?;
+        Some(&mut self.data[idx])
+    }
+
+    /// Iterate over every cell with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ([CoordInt; 2], &T)> {
+        let width = self.size[0] as usize;
+        self.data.iter().enumerate().map(move |(idx, value)| {
+            let x = (idx % width) as CoordInt;
+            let y = (idx / width) as CoordInt;
+            ([x, y], value)
+        })
+    }
+
+    /// Positions of the neighbors of the cell by each side of it. Neighbors
+    /// which lay outside of the grid are None.
+    pub fn neighbors(&self, position: [CoordInt; 2]) -> EnumMap<HexSide, Option<[CoordInt; 2]>> {
+        neighbor_positions(position).map(|_, neighbor| neighbor.filter(|pos| self.contains(*pos)))
+    }
+
+    fn idx(&self, position @ [x, y]: [CoordInt; 2]) -> Option<usize> {
+        self.contains(position)
+            .then(|| x as usize + y as usize * self.size[0] as usize)
+    }
+}
+
+impl<T> Index<[CoordInt; 2]> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: [CoordInt; 2]) -> &Self::Output {
+        self.get(position)
+            .unwrap_or_else(|| panic!("{:?} is outside of the grid of size {:?}", position, self.size))
+    }
+}
+
+impl<T> IndexMut<[CoordInt; 2]> for Grid<T> {
+    fn index_mut(&mut self, position: [CoordInt; 2]) -> &mut Self::Output {
+        let size = self.size;
+        self.get_mut(position)
+            .unwrap_or_else(|| panic!("{:?} is outside of the grid of size {:?}", position, size))
+    }
+}
+
+/// Given the coordinate of the tile, produce the set of neighbor coordinates
+/// with the correlation as which side it is neighboring with. Neighbors which
+/// would lay outside of the representable coordinates (e.g. to the west of
+/// the tile with x == 0) are None.
+pub fn neighbor_positions([x, y]: [CoordInt; 2]) -> EnumMap<HexSide, Option<[CoordInt; 2]>> {
+    use HexSide::*;
+    let offset = |dx, dy| {
+        Some([
+            x.checked_add_signed(dx)?,
+            y.checked_add_signed(dy)?,
+        ])
+    };
+    if y % 2 == 0 {
+        enum_map! {
+            NorthWest => offset(-1, -1),
+            NorthEast => offset(0, -1),
+            West => offset(-1, 0),
+            East => offset(1, 0),
+            SouthWest => offset(-1, 1),
+            SouthEast => offset(0, 1),
+        }
+    } else {
+        enum_map! {
+            NorthWest => offset(0, -1),
+            NorthEast => offset(1, -1),
+            West => offset(-1, 0),
+            East => offset(1, 0),
+            SouthWest => offset(0, 1),
+            SouthEast => offset(1, 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::HexSide;
+
+    use super::Grid;
+
+    #[test]
+    fn bounds_checked_access() {
+        let mut grid = Grid::new([3, 2], 0);
+        grid[[2, 1]] = 5;
+        assert_eq!(grid.get([2, 1]), Some(&5));
+        assert_eq!(grid.get([3, 0]), None);
+        assert_eq!(grid.get([0, 2]), None);
+        assert_eq!(grid.iter().count(), 6);
+        assert_eq!(grid.iter().find(|(_, value)| **value == 5), Some(([2, 1], &5)));
+    }
+
+    #[test]
+    fn neighbors_stay_within_the_grid() {
+        let grid = Grid::new([3, 3], ());
+        let corner = grid.neighbors([0, 0]);
+        assert_eq!(corner[HexSide::East], Some([1, 0]));
+        assert_eq!(corner[HexSide::SouthEast], Some([0, 1]));
+        assert_eq!(corner.values().flatten().count(), 2);
+
+        // Odd rows are shifted to the east
+        let middle = grid.neighbors([1, 1]);
+        assert_eq!(middle.values().flatten().count(), 6);
+        assert_eq!(middle[HexSide::NorthEast], Some([2, 0]));
+
+        let edge = grid.neighbors([2, 1]);
+        assert_eq!(edge[HexSide::East], None);
+        assert_eq!(edge[HexSide::NorthEast], None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn indexing_outside_panics() {
+        let grid = Grid::new([2, 2], 0);
+        let _ = grid[[2, 0]];
+    }
+}
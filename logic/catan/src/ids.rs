@@ -5,31 +5,166 @@
 /// This is mostly to give semantic meaning to types such as Map<EntityID, Entity>,
 /// where we can now see, that EntityID and Entity are correlated and EntityID
 /// shouldn't be used in places where it is not expected to be seen.
+///
+/// The maximum value of the backing integer is reserved, so that an Option
+/// of the ID (e.g. the owner of the road, or the neighbor of the tile) takes
+/// no more space than the ID itself. The IDs are still constructed and
+/// serialized as plain integers: `RoadID(3)` is a function producing the ID.
 macro_rules! int_wrapper {
-    ($name: ident, $ty: ty) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, ::serde::Deserialize, Hash)]
-        pub struct $name(pub $ty);
+    ($name: ident, $ty: ty, $non_zero: ty) => {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "typescript", derive(::ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/", type = "number"))]
+        pub struct $name {
+            /// The value, with every bit flipped. The reserved maximum is
+            /// then the zero, which the compiler uses as the niche.
+            flipped: $non_zero,
+        }
+
+        /// Construct the ID. Panics if the value is the reserved maximum of
+        /// the backing integer.
+        #[allow(non_snake_case)]
+        pub const fn $name(value: $ty) -> $name {
+            match <$non_zero>::new(!value) {
+                Some(flipped) => $name { flipped },
+                None => panic!(concat!(stringify!($name), " can't be the maximum of its integer")),
+            }
+        }
+
+        impl $name {
+            /// The largest value the ID may have
+            pub const MAX: $ty = <$ty>::MAX - 1;
+
+            pub const fn get(self) -> $ty {
+                !self.flipped.get()
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.get()).finish()
+            }
+        }
+
+        impl ::std::hash::Hash for $name {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                self.get().hash(state)
+            }
+        }
+
+        impl ::serde::Serialize for $name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.get().serialize(serializer)
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <$ty>::deserialize(deserializer)?;
+                <$name>::try_from(value as usize).map_err(::serde::de::Error::custom)
+            }
+        }
 
         impl From<$name> for usize {
             fn from(v: $name) -> Self {
-                v.0 as usize
+                v.get() as usize
             }
         }
 
         impl TryFrom<usize> for $name {
-            type Error = ::std::num::TryFromIntError;
+            type Error = IdOutOfRange;
 
             fn try_from(value: usize) -> Result<Self, Self::Error> {
-                value.try_into().map($name)
+                match <$ty>::try_from(value) {
+                    Ok(id) if id <= <$name>::MAX => Ok($name(id)),
+                    _ => Err(IdOutOfRange(value)),
+                }
             }
         }
 
     };
 }
 
-int_wrapper!(TileID, u8);
-int_wrapper!(ResourceTileID, u8);
-int_wrapper!(RoadID, u16);
-int_wrapper!(SettlePlaceID, u16);
-int_wrapper!(DiceMarkerID, u8);
-int_wrapper!(PlayerID, u8);
+/// The value is larger than the ID of the kind can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{0} is out of the range of the ID")]
+pub struct IdOutOfRange(pub usize);
+
+/// Integers backing each kind of ID, and the tile coordinates. By default they
+/// are as compact as the standard maps allow. The `wide-ids` feature doubles
+/// the width of every board entity ID and of the coordinates, for the servers
+/// hosting maps which don't fit otherwise.
+#[cfg(not(feature = "wide-ids"))]
+mod width {
+    use std::num::{NonZeroU16, NonZeroU8};
+
+    pub type TileInt = u8;
+    pub type RoadInt = u16;
+    pub type SettlePlaceInt = u16;
+    pub type DiceMarkerInt = u8;
+    pub type CoordInt = u8;
+
+    pub(super) type NonZeroTileInt = NonZeroU8;
+    pub(super) type NonZeroRoadInt = NonZeroU16;
+    pub(super) type NonZeroSettlePlaceInt = NonZeroU16;
+    pub(super) type NonZeroDiceMarkerInt = NonZeroU8;
+}
+
+#[cfg(feature = "wide-ids")]
+mod width {
+    use std::num::{NonZeroU16, NonZeroU32};
+
+    pub type TileInt = u16;
+    pub type RoadInt = u32;
+    pub type SettlePlaceInt = u32;
+    pub type DiceMarkerInt = u16;
+    pub type CoordInt = u16;
+
+    pub(super) type NonZeroTileInt = NonZeroU16;
+    pub(super) type NonZeroRoadInt = NonZeroU32;
+    pub(super) type NonZeroSettlePlaceInt = NonZeroU32;
+    pub(super) type NonZeroDiceMarkerInt = NonZeroU16;
+}
+
+pub use width::*;
+/// There are never more than a handful of players, regardless of the map size
+pub type PlayerInt = u8;
+
+int_wrapper!(TileID, TileInt, NonZeroTileInt);
+int_wrapper!(ResourceTileID, TileInt, NonZeroTileInt);
+int_wrapper!(RoadID, RoadInt, NonZeroRoadInt);
+int_wrapper!(SettlePlaceID, SettlePlaceInt, NonZeroSettlePlaceInt);
+int_wrapper!(DiceMarkerID, DiceMarkerInt, NonZeroDiceMarkerInt);
+int_wrapper!(PlayerID, PlayerInt, ::std::num::NonZeroU8);
+
+#[cfg(test)]
+mod test {
+    use std::mem::size_of;
+
+    use super::{IdOutOfRange, PlayerID, RoadID, TileID};
+
+    #[test]
+    fn optional_ids_take_no_extra_space() {
+        assert_eq!(size_of::<Option<RoadID>>(), size_of::<RoadID>());
+        assert_eq!(size_of::<Option<PlayerID>>(), 1);
+        assert_eq!(size_of::<Option<TileID>>(), size_of::<TileID>());
+    }
+
+    #[test]
+    fn ids_behave_as_plain_integers() {
+        assert_eq!(RoadID(7).get(), 7);
+        assert_eq!(usize::from(TileID(TileID::MAX)), TileID::MAX as usize);
+        assert_eq!(format!("{:?}", PlayerID(2)), "PlayerID(2)");
+        assert_eq!(serde_json::to_string(&RoadID(12)).unwrap(), "12");
+        assert_eq!(serde_json::from_str::<RoadID>("12").unwrap(), RoadID(12));
+
+        let reserved = TileID::MAX as usize + 1;
+        assert_eq!(TileID::try_from(reserved), Err(IdOutOfRange(reserved)));
+        assert!(serde_json::from_str::<TileID>(&reserved.to_string()).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn reserved_maximum_is_rejected() {
+        PlayerID(u8::MAX);
+    }
+}
@@ -0,0 +1,219 @@
+use enum_map::{enum_map, EnumMap};
+
+use crate::{
+    adjacency_list::AdjacencyList,
+    coordinates::Axial,
+    ids::{CoordInt, RoadID, SettlePlaceID, TileID},
+    relations::{GameState, RoadRelations, SettleRelations, TileRelations},
+    types::HexVertex,
+};
+
+/// A point on the 2D plane. The y axis points downwards, the same as
+/// it does on the screen (so that north is at the top).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn distance(self, other: Self) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    /// The point halfway between the two points
+    pub fn midpoint(self, other: Self) -> Self {
+        Self {
+            x: (self.x + other.x) / 2.,
+            y: (self.y + other.y) / 2.,
+        }
+    }
+}
+
+/// Geometry of the rendered board. Tiles are pointy-top hexagons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    /// Distance from the center of the tile to any of its vertices
+    pub size: f64,
+    /// Where the tile with axial coordinates (0, 0) is placed
+    pub origin: Point,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            size: 1.,
+            origin: Point::default(),
+        }
+    }
+}
+
+impl Layout {
+    pub fn new(size: f64, origin: Point) -> Self {
+        Self { size, origin }
+    }
+
+    /// The center of the tile, positioned at the specified offset coordinates
+    pub fn tile_center(&self, position: [CoordInt; 2]) -> Point {
+        let Axial { q, r } = Axial::from_offset(position);
+        let (q, r) = (q as f64, r as f64);
+        Point {
+            x: self.origin.x + self.size * 3f64.sqrt() * (q + r / 2.),
+            y: self.origin.y + self.size * 1.5 * r,
+        }
+    }
+
+    /// The vertex of the tile, positioned at the specified offset coordinates
+    pub fn tile_vertex(&self, position: [CoordInt; 2], vertex: HexVertex) -> Point {
+        let center = self.tile_center(position);
+        let [dx, dy] = vertex_offsets()[vertex];
+        Point {
+            x: center.x + self.size * dx,
+            y: center.y + self.size * dy,
+        }
+    }
+
+    /// All six corners of the tile in clockwise order, starting from north
+    pub fn tile_corners(&self, position: [CoordInt; 2]) -> [Point; 6] {
+        [
+            HexVertex::North,
+            HexVertex::NorthEast,
+            HexVertex::SouthEast,
+            HexVertex::South,
+            HexVertex::SouthWest,
+            HexVertex::NorthWest,
+        ]
+        .map(|vertex| self.tile_vertex(position, vertex))
+    }
+
+    /// Precompute positions of every entity on the decoded board
+    pub fn board(&self, state: &GameState) -> BoardLayout {
+        let tiles = AdjacencyList::from_vec(
+            state
+                .tile
+                .position
+                .values()
+                .map(|position| self.tile_center(*position))
+                .collect(),
+        );
+
+        let mut settle_places = SettleRelations::from_vec(vec![
+            Point::default();
+            state.settle_place.roads.len()
+        ]);
+        for (tile, vertices) in &state.tile.settle_places {
+            for (vertex, settle_place) in vertices {
+                settle_places[*settle_place] =
+                    self.tile_vertex(state.tile.position[tile], vertex);
+            }
+        }
+
+        let roads = AdjacencyList::from_vec(
+            state
+                .road
+                .settle_places
+                .values()
+                .map(|ends| ends.map(|settle_place| settle_places[settle_place]))
+                .collect(),
+        );
+
+        BoardLayout {
+            tiles,
+            settle_places,
+            roads,
+        }
+    }
+}
+
+/// The positions of every tile, settle place and road of the board
+/// for the given Layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardLayout {
+    /// Centers of the tiles
+    pub tiles: TileRelations<Point>,
+    pub settle_places: SettleRelations<Point>,
+    /// Both of the ends of the road, in the same order as the
+    /// settle places they connect
+    pub roads: RoadRelations<[Point; 2]>,
+}
+
+impl BoardLayout {
+    pub fn tile(&self, tile: TileID) -> Point {
+        self.tiles[tile]
+    }
+
+    pub fn settle_place(&self, settle_place: SettlePlaceID) -> Point {
+        self.settle_places[settle_place]
+    }
+
+    /// The middle of the road
+    pub fn road(&self, road: RoadID) -> Point {
+        let [a, b] = self.roads[road];
+        a.midpoint(b)
+    }
+}
+
+/// Offsets of each vertex from the center of the tile of size 1
+fn vertex_offsets() -> EnumMap<HexVertex, [f64; 2]> {
+    let half_width = 3f64.sqrt() / 2.;
+    enum_map! {
+        HexVertex::North => [0., -1.],
+        HexVertex::NorthEast => [half_width, -0.5],
+        HexVertex::SouthEast => [half_width, 0.5],
+        HexVertex::South => [0., 1.],
+        HexVertex::SouthWest => [-half_width, 0.5],
+        HexVertex::NorthWest => [-half_width, -0.5],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{decode_config, ids::TileID, MapConfig};
+
+    use super::{Layout, Point};
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test]
+    fn neighboring_tiles_are_one_width_apart() {
+        let layout = Layout::new(10., Point::new(5., 5.));
+        let center = layout.tile_center([3, 3]);
+        for position in [[3, 2], [4, 2], [2, 3], [4, 3], [3, 4], [4, 4]] {
+            let distance = center.distance(layout.tile_center(position));
+            assert!((distance - 10. * 3f64.sqrt()).abs() < EPSILON);
+        }
+        // Odd rows are shifted half a tile to the east
+        let odd = layout.tile_center([0, 1]);
+        let even = layout.tile_center([0, 2]);
+        assert!((odd.x - even.x - 5. * 3f64.sqrt()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn shared_vertices_match() {
+        let state = decode_config(MapConfig::standard(), 4).unwrap();
+        let layout = Layout::default();
+        let board = layout.board(&state);
+
+        // Every tile sees its settle places at the same positions other
+        // tiles sharing those settle places do
+        for (tile, vertices) in &state.tile.settle_places {
+            let position = state.tile.position[tile];
+            for (vertex, settle_place) in vertices {
+                let expected = layout.tile_vertex(position, vertex);
+                assert!(board.settle_place(*settle_place).distance(expected) < EPSILON);
+            }
+        }
+
+        for (road, _) in &state.road.settle_places {
+            let [a, b] = board.roads[road];
+            assert!((a.distance(b) - 1.).abs() < EPSILON);
+        }
+
+        let center = board.tile(TileID(9));
+        assert_eq!(center, layout.tile_center([3, 3]));
+    }
+}
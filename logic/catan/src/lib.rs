@@ -1,23 +1,80 @@
+//! Rules engine of the game of Catan.
+//!
+//! Everything reachable from the crate root, except for the [`unstable`]
+//! module, is considered to be the stable API and follows semver. Enums which
+//! are expected to grow with expansions or new failure modes are marked
+//! `#[non_exhaustive]`, so adding variants to them is not a breaking change.
+//!
+//! The [`unstable`] module exposes the underlying storage of the game state
+//! (relation tables and the containers they are built from). Those are
+//! needed to inspect the state today, but their layout may change between
+//! any two releases.
+
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::VecDeque,
     ops::{Index, IndexMut},
 };
 
+use bitset::BitSet;
+use grid::Grid;
+
 use array_vec::ArrayVec;
 use enum_map::{enum_map, EnumMap};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 pub(crate) mod adjacency_list;
 use adjacency_list::AdjacencyList;
-pub(crate) mod ids;
+pub mod ids;
 use ids::*;
-pub(crate) mod types;
+pub mod types;
 use types::*;
 pub(crate) mod relations;
 use relations::*;
 pub(crate) mod array_vec;
+pub(crate) mod rng;
+pub(crate) mod bitset;
+pub mod topology;
+pub mod coordinates;
+pub mod transform;
+pub mod grid;
+pub mod layout;
+pub mod ascii;
+pub mod delta;
+pub mod config;
+pub mod board;
+pub mod rules;
+pub mod transactions;
+pub mod network;
+pub mod fairness;
+pub mod generate;
+pub mod fingerprint;
+pub mod error;
+pub mod pregame;
+pub mod save;
+pub mod replay;
+pub mod features;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+#[cfg(feature = "svg")]
+pub mod svg;
+use rules::GameRules;
+use topology::{BoardTopology, TopologyDiagnostics};
+
+pub use config::{LoadConfigError, MapConfigBuilder, MapConfigError};
+pub use board::Board;
+pub use relations::GameState;
+
+/// Internal storage of the game state. There are no stability guarantees
+/// for anything in this module.
+pub mod unstable {
+    pub use crate::adjacency_list::*;
+    pub use crate::array_vec::{ArrayVec, CapacityError};
+    pub use crate::bitset::BitSet;
+    pub use crate::relations::*;
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/"))]
 pub struct TileMap<T> {
     #[serde(default)]
     pub field: T,
@@ -33,16 +90,49 @@ pub struct TileMap<T> {
     pub desert: T,
 }
 
-/// The configuration of any given map stored usually as as json file
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+impl<T> Index<TileTerrain> for TileMap<T> {
+    type Output = T;
+
+    fn index(&self, terrain: TileTerrain) -> &T {
+        match terrain {
+            TileTerrain::Field => &self.field,
+            TileTerrain::Pasture => &self.pasture,
+            TileTerrain::Forest => &self.forest,
+            TileTerrain::Mesa => &self.mesa,
+            TileTerrain::Mountains => &self.mountains,
+            TileTerrain::Desert => &self.desert,
+        }
+    }
+}
+
+impl<T> IndexMut<TileTerrain> for TileMap<T> {
+    fn index_mut(&mut self, terrain: TileTerrain) -> &mut T {
+        match terrain {
+            TileTerrain::Field => &mut self.field,
+            TileTerrain::Pasture => &mut self.pasture,
+            TileTerrain::Forest => &mut self.forest,
+            TileTerrain::Mesa => &mut self.mesa,
+            TileTerrain::Mountains => &mut self.mountains,
+            TileTerrain::Desert => &mut self.desert,
+        }
+    }
+}
+
+/// The configuration of any given map stored usually as as json file.
+///
+/// Serializing the config and deserializing it back always produces the same
+/// config. The canonical JSON form, as produced by [`MapConfig::to_json`],
+/// lists every field (even the defaulted ones) in the declaration order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/"))]
 pub struct MapConfig {
     /// The amount of different terrains in use in specified map
-    tile_bank: TileMap<u8>,
-    map_size: [u8; 2],
+    tile_bank: TileMap<TileInt>,
+    map_size: [CoordInt; 2],
     /// Positions of all of the tiles. Index signifies TileID,
     /// while value, is the coordinated in a squared-off map
-    tile_placement: Vec<[u8; 2]>,
+    tile_placement: Vec<[CoordInt; 2]>,
     /// If randomization is turned off, how will the distribution
     /// of terrains lay itself.
     default_tiles: Vec<TileTerrain>,
@@ -58,63 +148,77 @@ pub struct MapConfig {
     /// If randomization is turned off, how will the distribution
     /// of harbours lay itself.
     default_harbours: Vec<Harbour>,
+    #[serde(default)]
+    #[cfg_attr(feature = "typescript", ts(type = "Array<number | null>"))]
+    /// Dice markers laid on top of each of the tiles, if randomization is
+    /// turned off. Index signifies TileID, and deserts have no marker.
+    /// If none are specified, the markers of the base game are laid out
+    /// in the order of TileIDs instead.
+    default_dice_markers: Vec<Option<DiceMarker>>,
+    #[serde(default)]
+    /// The desert the robber starts the game on, if the rules place it on
+    /// a desert. If not specified, the robber starts on the first desert.
+    robber_tile: Option<TileID>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
 pub enum DecodeConfigError {
+    #[error("{0} players can't play the game")]
     InvalidPlayerCount(u8),
+    /// The config contradicts itself, see [`MapConfig::validate`]
+    #[error("map config is invalid")]
+    InvalidConfig(#[source] MapConfigError),
+    /// Settle place is connected to more than three roads
+    #[error("settle place {0:?} is connected to more than three roads")]
+    TooManyRoads(SettlePlaceID),
+    /// The decoded board does not satisfy the identities of a planar hex board.
+    /// This signifies the bug in the decoding, rather than in the config.
+    #[error("decoded board is not a consistent hex board")]
+    TopologyInconsistent(#[source] TopologyDiagnostics),
 }
 
-/// Given map config, randomization preference, and player count, generate game state.
+/// Least amount of players the game can be played by
+pub const MIN_PLAYERS: PlayerInt = 2;
+/// Most players the game can be played by
+pub const MAX_PLAYERS: PlayerInt = 4;
+
+/// Given map config, randomization preference, and player count, generate game state
+/// played by the rules of the base game.
 pub fn decode_config(config: MapConfig, player_count: u8) -> Result<GameState, DecodeConfigError> {
-    use DecodeConfigError::*;
+    decode_config_with_rules(config, player_count, &GameRules::default())
+}
 
-    if !(2..=4).contains(&player_count) {
-        return Err(InvalidPlayerCount(player_count));
+/// Given map config, player count and the rules, generate game state.
+/// The board is decoded anew on every call, use [`BoardTopology`] to start
+/// many games on the same map.
+pub fn decode_config_with_rules(
+    config: MapConfig,
+    player_count: u8,
+    rules: &GameRules,
+) -> Result<GameState, DecodeConfigError> {
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&player_count) {
+        return Err(DecodeConfigError::InvalidPlayerCount(player_count));
     }
-
-    // Until randomization is implemented, just provide the default distribution of terrains.
-    let resource = AdjacencyList::from_vec(config.default_tiles);
-    let TileTraversalResult {
-        tile_settle_places,
-        tile_roads,
-        road_settle_places,
-        settle_places_count,
-    } = traverse_tiles(config.map_size, config.tile_placement);
-
-    let tile_relations = TileEntities {
-        resource,
-        roads: tile_roads,
-        settle_places: tile_settle_places,
-    };
-
-    let settle_relations = SettlePlaceEntities {
-        roads: derive_settle_place_roads_relations(&road_settle_places, settle_places_count),
-    };
-
-    let road_relations = RoadEntities {
-        settle_places: road_settle_places,
-    };
-
-    let map = GameState {
-        tile: tile_relations,
-        road: road_relations,
-        settle_place: settle_relations,
-        ..Default::default()
-    };
-
-    Ok(map)
+    BoardTopology::new(config)?.instantiate(player_count, rules)
 }
 
 #[derive(Debug, Clone, Copy)]
 enum VisitStatus {
     Processed(TileID),
-    NotVisited(TileID, [u8; 2]),
+    NotVisited(TileID, [CoordInt; 2]),
     NotATile,
 }
 
 impl VisitStatus {
-    fn not_visited(self) -> Option<(TileID, [u8; 2])> {
+    fn tile(self) -> Option<TileID> {
+        match self {
+            Self::Processed(id) | Self::NotVisited(id, _) => Some(id),
+            Self::NotATile => None,
+        }
+    }
+
+    fn not_visited(self) -> Option<(TileID, [CoordInt; 2])> {
         if let Self::NotVisited(id, pos) = self {
             Some((id, pos))
         } else {
@@ -123,46 +227,70 @@ impl VisitStatus {
     }
 }
 
-struct TileTraversalResult {
+pub(crate) struct TileTraversalResult {
     tile_settle_places: TileRelations<EnumMap<HexVertex, SettlePlaceID>>,
     tile_roads: TileRelations<EnumMap<HexSide, RoadID>>,
+    tile_neighbors: TileRelations<EnumMap<HexSide, Option<TileID>>>,
     road_settle_places: RoadRelations<[SettlePlaceID; 2]>,
-    settle_places_count: u16,
+    settle_place_tiles: SettleRelations<ArrayVec<TileID, 3>>,
+    settle_places_count: SettlePlaceInt,
+    tile_grid: Grid<Option<TileID>>,
 }
 
-/// Do a graph traversal (BSF) of tiles, while filling in the relations between tiles, roads and settle places
-fn traverse_tiles(map_size: [u8; 2], tile_placement: Vec<[u8; 2]>) -> TileTraversalResult {
+/// Do a graph traversal (BSF) of tiles, while filling in the relations between tiles, roads and settle places.
+/// Every island of the map is traversed, one after another.
+pub(crate) fn traverse_tiles(map_size: [CoordInt; 2], tile_placement: &[[CoordInt; 2]]) -> TileTraversalResult {
     use VisitStatus::*;
 
-    let mut queue = VecDeque::new();
-    queue.push_back((TileID(0), tile_placement[0]));
-
+    let tile_count = tile_placement.len();
     let map_2d = derive_2d_map(map_size, tile_placement);
 
-    let mut processed_tiles = HashSet::new();
+    // Tiles may be queued once by each of their neighbors, but most of them are
+    // processed long before all of their neighbors are
+    let mut queue = VecDeque::with_capacity(tile_count);
+    // Indexed by TileID rather than hashed, so that nothing about the traversal
+    // depends on the hasher, and the decoded IDs are the same on every platform
+    let mut processed_tiles = BitSet::<TileID>::empty(tile_count);
+    // Tiles which are not reachable from the already processed ones (i.e. other
+    // islands) seed the traversal of their own, in the order of their IDs
+    let mut seeds = (0..tile_count).map(|idx| TileID(idx as TileInt));
     let mut settle_places_count = 0;
-    // Relationships between tiles and settle places located at the vertexes of said tile
-    let mut tile_settle_places = TileRelations::<EnumMap<HexVertex, SettlePlaceID>>::new();
+    // Relationships between tiles and settle places located at the vertexes of said tile.
+    // Tiles are processed in the order of traversal, rather than in the order of their IDs,
+    // so the relations are stored by TileID index and are filled in as we go.
+    let mut tile_settle_places =
+        TileRelations::<Option<EnumMap<HexVertex, SettlePlaceID>>>::from_vec(vec![None; tile_count]);
     // Relationships between tiles and roads located at the sides of said tile
-    let mut tile_roads = TileRelations::<EnumMap<HexSide, RoadID>>::new();
+    let mut tile_roads =
+        TileRelations::<Option<EnumMap<HexSide, RoadID>>>::from_vec(vec![None; tile_count]);
+    // Relationships between tiles and the tiles laying next to each of their sides
+    let mut tile_neighbors =
+        TileRelations::<Option<EnumMap<HexSide, Option<TileID>>>>::from_vec(vec![None; tile_count]);
     // Relationships between roads and the settle places it is connecting.
     let mut road_settle_places = RoadRelations::<[SettlePlaceID; 2]>::new();
-
-    // While queue of tiles to be processed is not empty
-    while let Some((tile_id, pos)) = queue.pop_front() {
-        // If tile is already processed (HashSet::insert returns true if value wasn't in the set),
-        // skip processing it
-        let not_processed = processed_tiles.insert(tile_id);
-        if !not_processed {
+    // Relationships between settle places and up to three tiles they are a vertex of.
+    let mut settle_place_tiles = SettleRelations::<ArrayVec<TileID, 3>>::new();
+
+    // While there are tiles to be processed, either queued or not reached yet
+    while let Some((tile_id, pos)) = queue.pop_front().or_else(|| {
+        let seed = seeds.find(|tile| !processed_tiles.contains(*tile))?;
+        Some((seed, tile_placement[usize::from(seed)]))
+    }) {
+        // If tile is already processed, skip processing it
+        if !processed_tiles.insert(tile_id) {
             continue;
         }
 
         // For each neighbor tile might have, determine the status of said tile.
         // Either processed, not visited, or not a tile completely.
-        let neighbor_status = neighbor_positions(pos).map(|_, pos| match map_2d[pos] {
-            Some(tile_id) if processed_tiles.contains(&tile_id) => Processed(tile_id),
-            Some(tile_id) => NotVisited(tile_id, pos),
-            None => NotATile,
+        // Positions outside of the map bounds are treated the same as empty ones.
+        let neighbor_status = map_2d.neighbors(pos).map(|_, pos| {
+            let tile = pos.and_then(|pos| map_2d[pos]);
+            match (tile, pos) {
+                (Some(tile_id), _) if processed_tiles.contains(tile_id) => Processed(tile_id),
+                (Some(tile_id), Some(pos)) => NotVisited(tile_id, pos),
+                _ => NotATile,
+            }
         });
 
         // For each neighboring side, if the neighboring tile is not present, or is not processed,
@@ -172,23 +300,28 @@ fn traverse_tiles(map_size: [u8; 2], tile_placement: Vec<[u8; 2]>) -> TileTraver
         let settle_places =
             settle_places_lookup().map(|_, [(a_side, a_vert), (b_side, b_vert)]| {
                 if let Processed(neighbor_id) = neighbor_status[a_side] {
-                    tile_settle_places[neighbor_id][a_vert]
+                    processed(&tile_settle_places, neighbor_id)[a_vert]
                 } else if let Processed(neighbor_id) = neighbor_status[b_side] {
-                    tile_settle_places[neighbor_id][b_vert]
+                    processed(&tile_settle_places, neighbor_id)[b_vert]
                 } else {
                     let id = SettlePlaceID(settle_places_count);
                     settle_places_count += 1;
+                    settle_place_tiles.push(ArrayVec::new());
                     id
                 }
             });
 
+        for settle_place in settle_places.values() {
+            settle_place_tiles[*settle_place].push(tile_id);
+        }
+
         // Do the same trick (where we copy existing road IDs from our already
         // processed neighbors) as with the settle places, to the roads.
         // But, if the road were not previously constructed, also fill in 
         // relationship between road and two settle places it connects.
         let roads = neighbor_status.map(|side, status| {
             if let Processed(id) = status {
-                tile_roads[id][side.opposite()]
+                processed(&tile_roads, id)[side.opposite()]
             } else {
                 let connected_settle_places =
                     side.connected_vertices().map(|vert| settle_places[vert]);
@@ -196,8 +329,9 @@ fn traverse_tiles(map_size: [u8; 2], tile_placement: Vec<[u8; 2]>) -> TileTraver
             }
         });
 
-        tile_settle_places.push(settle_places);
-        tile_roads.push(roads);
+        tile_settle_places[tile_id] = Some(settle_places);
+        tile_roads[tile_id] = Some(roads);
+        tile_neighbors[tile_id] = Some(neighbor_status.map(|_, status| status.tile()));
 
         // Add to the queue all of the neighbors we haven't processed yet
         queue.extend(
@@ -207,20 +341,39 @@ fn traverse_tiles(map_size: [u8; 2], tile_placement: Vec<[u8; 2]>) -> TileTraver
         )
     }
 
+    // Every tile is processed, since every unprocessed one seeds the traversal.
+    // Should it not be, the tile would be dropped here, and it is up to the
+    // topology verification to report such inconsistencies.
     TileTraversalResult {
-        tile_settle_places,
-        tile_roads,
+        tile_settle_places: TileRelations::from_vec(
+            tile_settle_places.into_iter().filter_map(|(_, v)| v).collect(),
+        ),
+        tile_roads: TileRelations::from_vec(
+            tile_roads.into_iter().filter_map(|(_, v)| v).collect(),
+        ),
+        tile_neighbors: TileRelations::from_vec(
+            tile_neighbors.into_iter().filter_map(|(_, v)| v).collect(),
+        ),
         road_settle_places,
+        settle_place_tiles,
         settle_places_count,
+        tile_grid: map_2d,
     }
 }
 
+/// Relations of the tile which is known to be already processed by the traversal
+fn processed<V>(relations: &TileRelations<Option<V>>, tile_id: TileID) -> &V {
+    relations[tile_id]
+        .as_ref()
+        .expect("Processed tiles should have their relations filled in")
+}
+
 /// Given the relationships of RoadID -> SettlePlaceID produce the 
 /// inverse relationships of kind SettlePlaceID -> RoadID
-fn derive_settle_place_roads_relations(
+pub(crate) fn derive_settle_place_roads_relations(
     road_settle_places: &AdjacencyList<RoadID, [SettlePlaceID; 2]>,
-    settle_places_count: u16,
-) -> AdjacencyList<SettlePlaceID, ArrayVec<RoadID, 3>> {
+    settle_places_count: SettlePlaceInt,
+) -> Result<AdjacencyList<SettlePlaceID, ArrayVec<RoadID, 3>>, DecodeConfigError> {
     // Create AdjacencyList of empty vecs, ot be filled in
     let mut settle_place_roads = AdjacencyList::from_vec(
         std::iter::repeat_with(ArrayVec::new)
@@ -228,52 +381,103 @@ fn derive_settle_place_roads_relations(
             .collect(),
     );
 
-    for (road, [settle_place_a, settle_place_b]) in road_settle_places {
-        settle_place_roads[*settle_place_a].push(road);
-        settle_place_roads[*settle_place_b].push(road);
+    // Settle place can be connected to at most three roads. Malformed configs
+    // may still claim more than that, which is reported instead of panicking.
+    for (road, settle_places) in road_settle_places {
+        for settle_place in *settle_places {
+            settle_place_roads[settle_place]
+                .try_push(road)
+                .map_err(|_| DecodeConfigError::TooManyRoads(settle_place))?;
+        }
     }
 
-    settle_place_roads
-}
-
-/// Given the size of the map and the positions of tiles within, produce
-/// 2D Matrix of map size, where each value is either the id of a tile
-/// in the position, or nothing, if no such tile is located there
-fn derive_2d_map([width, height]: [u8; 2], tile_placement: Vec<[u8; 2]>) -> Matrix<Option<TileID>> {
-    let width = width as usize;
-    let height = height as usize;
-    let mut map_2d = Matrix::from_vec(vec![None; width * height], width);
-    for (idx, pos) in tile_placement.into_iter().enumerate() {
-        map_2d[pos] = Some(TileID(idx.try_into().unwrap()))
-    }
-    map_2d
+    Ok(settle_place_roads)
 }
 
-/// Just a small simple convenience struct which allows indexing
-/// it with pairs of u8's which represent 2d coordinates
-struct Matrix<T> {
-    width: usize,
-    data: Vec<T>,
+pub(crate) struct ResourceTileDerivation {
+    pub tile_resource_tiles: TileRelations<Option<ResourceTileID>>,
+    pub resource_tiles: ResourceTileEntities,
+    pub dice_markers: DiceMarkerEntities,
 }
 
-impl<T> Matrix<T> {
-    fn from_vec(data: Vec<T>, width: usize) -> Self {
-        Self { width, data }
+/// Number the tiles producing resources (every tile, except for the deserts) in
+/// the order of their TileIDs, and lay the dice markers on them. The markers are
+/// validated to be laid exactly on the resource tiles, so the marker with the
+/// DiceMarkerID is laid on the resource tile with the same ID.
+pub(crate) fn derive_resource_tiles(
+    resource: &TileRelations<TileTerrain>,
+    dice_marker: &TileRelations<Option<DiceMarker>>,
+) -> ResourceTileDerivation {
+    let mut tile_resource_tiles = TileRelations::new();
+    let mut resource_tiles = ResourceTileEntities::default();
+    let mut dice_markers = DiceMarkerEntities::default();
+    for (tile_id, terrain) in resource {
+        if *terrain == TileTerrain::Desert {
+            tile_resource_tiles.push(None);
+            continue;
+        }
+        let resource_tile_id = resource_tiles.tile.push(tile_id);
+        tile_resource_tiles.push(Some(resource_tile_id));
+        if let Some(marker) = dice_marker[tile_id] {
+            dice_markers.values.push(marker);
+            dice_markers.place.push(resource_tile_id);
+        }
+    }
+    ResourceTileDerivation {
+        tile_resource_tiles,
+        resource_tiles,
+        dice_markers,
     }
 }
 
-impl<T> Index<[u8; 2]> for Matrix<T> {
-    type Output = T;
+/// Lay the harbours of the config on the decoded tiles. The config is
+/// validated to place every harbour on the shore, but the harbours are
+/// resolved against the tiles anyway, and the mismatch is reported rather
+/// than expected never to happen.
+pub(crate) fn derive_harbours(
+    config: &MapConfig,
+    tile: &TileEntities,
+) -> Result<HarbourEntities, DecodeConfigError> {
+    let settle_places = config
+        .harbour_placement
+        .iter()
+        .map(|harbour| resolve_harbour(tile, *harbour))
+        .collect::<Result<_, _>>()
+        .map_err(DecodeConfigError::InvalidConfig)?;
+    Ok(HarbourEntities {
+        settle_places,
+        kind: config.default_harbours.clone(),
+    })
+}
 
-    fn index(&self, [x, y]: [u8; 2]) -> &Self::Output {
-        &self.data[x as usize + (y as usize) * self.width]
+/// Both of the settle places served by the harbour, which are the ends of
+/// the side of the tile the harbour faces. The harbour has to be in the
+/// water (or beyond the map), facing one of the tiles.
+pub(crate) fn resolve_harbour(
+    tile: &TileEntities,
+    harbour: HarbourPlacement,
+) -> Result<[SettlePlaceID; 2], MapConfigError> {
+    let tile_at = |position| tile.grid.get(position).copied().flatten();
+    if tile_at(harbour.position()).is_some() {
+        return Err(MapConfigError::HarbourNotOnShore(harbour.position()));
     }
+    let facing = harbour
+        .tile_position()
+        .and_then(tile_at)
+        .ok_or(MapConfigError::HarbourNotFacingTile(harbour.position()))?;
+    let settle_places = tile.settle_places[facing];
+    Ok(harbour.tile_side().connected_vertices().map(|vertex| settle_places[vertex]))
 }
 
-impl<T> IndexMut<[u8; 2]> for Matrix<T> {
-    fn index_mut(&mut self, [x, y]: [u8; 2]) -> &mut Self::Output {
-        &mut self.data[x as usize + (y as usize) * self.width]
+/// Given the size of the map and the positions of tiles within, produce
+/// a grid of map size, where each value is either the id of a tile
+/// in the position, or nothing, if no such tile is located there
+fn derive_2d_map(map_size: [CoordInt; 2], tile_placement: &[[CoordInt; 2]]) -> Grid<Option<TileID>> {
+    let mut map_2d = Grid::new(map_size, None);
+    for (idx, pos) in tile_placement.iter().enumerate() {
+        map_2d[*pos] = Some(TileID(idx.try_into().unwrap()))
     }
+    map_2d
 }
 
 /// The mapping of tile vertex to the pair of neighboring sides which may
@@ -282,7 +486,7 @@ fn settle_places_lookup() -> EnumMap<HexVertex, [(HexSide, HexVertex); 2]> {
     enum_map! {
         HexVertex::North => {[
             (HexSide::NorthWest, HexVertex::SouthEast),
-            (HexSide::NorthEast, HexVertex::SouthEast)
+            (HexSide::NorthEast, HexVertex::SouthWest)
         ]},
         HexVertex::NorthEast => {[
             (HexSide::NorthEast, HexVertex::South),
@@ -293,8 +497,8 @@ fn settle_places_lookup() -> EnumMap<HexVertex, [(HexSide, HexVertex); 2]> {
             (HexSide::SouthEast, HexVertex::North)
         ]},
         HexVertex::South => {[
-            (HexSide::SouthEast, HexVertex::SouthWest),
-            (HexSide::SouthWest, HexVertex::North)
+            (HexSide::SouthEast, HexVertex::NorthWest),
+            (HexSide::SouthWest, HexVertex::NorthEast)
         ]},
         HexVertex::SouthWest => {[
             (HexSide::SouthWest, HexVertex::North),
@@ -307,38 +511,31 @@ fn settle_places_lookup() -> EnumMap<HexVertex, [(HexSide, HexVertex); 2]> {
     }
 }
 
-/// Given the coordinate of the tile, produce the set of neighbor coordinates 
-/// with the correlation as which side it is neighboring with.
-fn neighbor_positions([x, y]: [u8; 2]) -> EnumMap<HexSide, [u8; 2]> {
-    use HexSide::*;
-    if y % 2 == 0 {
-        enum_map! {
-            NorthWest => [x-1, y-1],
-            NorthEast => [x,y-1],
-            West => [x-1, y],
-            East => [x+1, y],
-            SouthWest => [x-1, y+1],
-            SouthEast => [x, y+1],
-        }
-    } else {
-        enum_map! {
-            NorthWest => [x, y-1],
-            NorthEast => [x+1,y-1],
-            West => [x-1, y],
-            East => [x+1, y],
-            SouthWest => [x, y+1],
-            SouthEast => [x+1, y+1],
-        }
-    }
-}
+// Servers hold the games in the task maps of async runtimes, and bots share
+// the state between worker threads, so these must stay Send and Sync.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<GameState>();
+    assert_send_sync::<MapConfig>();
+    assert_send_sync::<GameRules>();
+    assert_send_sync::<Board<'static>>();
+    assert_send_sync::<network::BuildableCache>();
+    assert_send_sync::<network::OccupancyIndex>();
+    assert_send_sync::<transactions::TradeRatios>();
+    assert_send_sync::<pregame::Lobby<String>>();
+    assert_send_sync::<save::SavedGame>();
+    assert_send_sync::<replay::Replay>();
+    assert_send_sync::<replay::ReplayCursor<'static>>();
+    assert_send_sync::<error::Error>();
+};
 
 #[cfg(test)]
 mod test {
     use enum_map::enum_map;
 
     use crate::{
-        array_vec::array_vec, decode_config, ids::RoadID, types::HexSide, AdjacencyList, HexVertex,
-        MapConfig, SettlePlaceID, TileMap, TileTerrain,
+        array_vec::array_vec, decode_config, derive_settle_place_roads_relations, ids::{CoordInt, RoadID, TileInt}, types::HexSide, AdjacencyList, HexVertex,
+        DecodeConfigError, MapConfig, MapConfigError, SettlePlaceID, TileID, TileMap, TileTerrain,
     };
 
     #[test]
@@ -354,6 +551,8 @@ mod test {
             fixed_tiles: TileMap::default(),
             harbour_placement: vec![],
             default_harbours: vec![],
+            default_dice_markers: vec![],
+            robber_tile: None,
         };
 
         let res = decode_config(config, 2).unwrap();
@@ -399,6 +598,11 @@ mod test {
             ])
         );
 
+        assert_eq!(
+            res.settle_place.tiles,
+            AdjacencyList::from_vec(vec![array_vec![TileID(0)]; 6])
+        );
+
         assert_eq!(
             res.settle_place.roads,
             AdjacencyList::from_vec(vec![
@@ -425,6 +629,8 @@ mod test {
             fixed_tiles: TileMap::default(),
             harbour_placement: vec![],
             default_harbours: vec![],
+            default_dice_markers: vec![],
+            robber_tile: None,
         };
 
         let res = decode_config(config, 2).unwrap();
@@ -494,4 +700,149 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn decode_map_touching_the_edges() {
+        let config = MapConfig {
+            tile_bank: TileMap {
+                desert: 3,
+                ..Default::default()
+            },
+            map_size: [2, 2],
+            tile_placement: vec![[0, 0], [1, 0], [0, 1]],
+            default_tiles: vec![TileTerrain::Desert, TileTerrain::Desert, TileTerrain::Desert],
+            fixed_tiles: TileMap::default(),
+            harbour_placement: vec![],
+            default_harbours: vec![],
+            default_dice_markers: vec![],
+            robber_tile: None,
+        };
+
+        let res = decode_config(config, 2).unwrap();
+
+        assert_eq!(res.tile.roads.len(), 3);
+        assert_eq!(res.road.settle_places.len(), 15);
+        assert_eq!(res.settle_place.roads.len(), 13);
+        assert_eq!(
+            res.tile.roads[TileID(0)][HexSide::East],
+            res.tile.roads[TileID(1)][HexSide::West]
+        );
+        assert_eq!(
+            res.tile.neighbors,
+            AdjacencyList::from_vec(vec![
+                enum_map! {
+                    HexSide::East => Some(TileID(1)),
+                    HexSide::SouthEast => Some(TileID(2)),
+                    _ => None,
+                },
+                enum_map! {
+                    HexSide::West => Some(TileID(0)),
+                    HexSide::SouthWest => Some(TileID(2)),
+                    _ => None,
+                },
+                enum_map! {
+                    HexSide::NorthWest => Some(TileID(0)),
+                    HexSide::NorthEast => Some(TileID(1)),
+                    _ => None,
+                },
+            ])
+        );
+        assert_eq!(
+            res.tile.roads[TileID(0)][HexSide::SouthEast],
+            res.tile.roads[TileID(2)][HexSide::NorthWest]
+        );
+    }
+
+    #[test]
+    fn settle_place_with_too_many_roads() {
+        let road_settle_places = AdjacencyList::from_vec(vec![
+            [SettlePlaceID(0), SettlePlaceID(1)],
+            [SettlePlaceID(0), SettlePlaceID(2)],
+            [SettlePlaceID(0), SettlePlaceID(3)],
+            [SettlePlaceID(4), SettlePlaceID(0)],
+        ]);
+
+        assert_eq!(
+            derive_settle_place_roads_relations(&road_settle_places, 5),
+            Err(DecodeConfigError::TooManyRoads(SettlePlaceID(0)))
+        );
+    }
+
+    #[test]
+    fn malformed_configs_are_rejected() {
+        let config = |map_size, tile_placement: Vec<[CoordInt; 2]>| MapConfig {
+            tile_bank: TileMap {
+                forest: tile_placement.len() as TileInt,
+                ..Default::default()
+            },
+            map_size,
+            default_tiles: vec![TileTerrain::Forest; tile_placement.len()],
+            tile_placement,
+            fixed_tiles: TileMap::default(),
+            harbour_placement: vec![],
+            default_harbours: vec![],
+            default_dice_markers: vec![],
+            robber_tile: None,
+        };
+
+        assert_eq!(
+            decode_config(config([2, 2], vec![[0, 0], [5, 255]]), 2),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::TileOutOfBounds([5, 255])))
+        );
+        assert_eq!(
+            decode_config(config([2, 2], vec![[0, 0], [0, 0]]), 2),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::DuplicateTile([0, 0])))
+        );
+        assert_eq!(
+            decode_config(config([2, 2], vec![]), 2),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::EmptyMap))
+        );
+        assert_eq!(
+            decode_config(config([0, 2], vec![[0, 0]]), 2),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::ZeroSizedMap([0, 2])))
+        );
+
+        let mut missing_default = config([2, 2], vec![[0, 0], [1, 0]]);
+        missing_default.default_tiles.pop();
+        assert_eq!(
+            decode_config(missing_default, 2),
+            Err(DecodeConfigError::InvalidConfig(MapConfigError::DefaultTilesMismatch {
+                placed: 2,
+                default: 1,
+            }))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wide-ids")]
+    fn maps_beyond_compact_ids_are_decoded() {
+        use crate::MapConfigBuilder;
+
+        // A strip of more tiles than u8 can address, far past the u8 coordinates
+        let config = (0..300)
+            .fold(MapConfigBuilder::new(), |builder, x| {
+                builder.add_tile([x, 400], TileTerrain::ALL[x as usize % TileTerrain::ALL.len()])
+            })
+            .build()
+            .unwrap();
+        let state = decode_config(config, 2).unwrap();
+        assert_eq!(state.tile.position.len(), 300);
+        assert_eq!(state.tile.position[TileID(299 as TileInt)], [299, 400]);
+
+        // The tile bank counts past u8 as well
+        let forest = (0..300)
+            .fold(MapConfigBuilder::new(), |builder, x| {
+                builder.add_tile([x % 20, x / 20], TileTerrain::Forest)
+            })
+            .build()
+            .unwrap();
+        assert_eq!(forest.tile_bank[TileTerrain::Forest], 300);
+        decode_config(forest, 2).unwrap();
+
+        let too_large = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .set_map_size([2000, 2000])
+            .build();
+        assert_eq!(too_large, Err(MapConfigError::MapTooLarge([2000, 2000])));
+    }
 }
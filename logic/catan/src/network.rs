@@ -0,0 +1,480 @@
+use crate::{
+    bitset::BitSet,
+    ids::{PlayerID, RoadID, SettlePlaceID},
+    relations::{GameState, PlayerRelations},
+    types::SettlePlace,
+};
+
+impl GameState {
+    /// Whether the road is connected to the player's road network: either end
+    /// of it is the player's settlement or town, or the player has another
+    /// road coming out of it. A junction settled by an opponent breaks the
+    /// network, so the player's roads on the other side of it don't count.
+    pub fn is_connected(&self, player: PlayerID, road: RoadID) -> bool {
+        self.road.settle_places[road]
+            .iter()
+            .any(|end| self.extends_network(player, *end, road))
+    }
+
+    /// Whether the player's network reaches the settle place by any
+    /// road other than the given one
+    fn extends_network(
+        &self,
+        player: PlayerID,
+        settle_place: SettlePlaceID,
+        road: RoadID,
+    ) -> bool {
+        match self.settle_place.occupancy[settle_place] {
+            SettlePlace::Settlement(settler) | SettlePlace::Town(settler) => settler == player,
+            SettlePlace::Empty => self.settle_place.roads[settle_place]
+                .as_ref()
+                .iter()
+                .any(|other| *other != road && self.road.owner[*other] == Some(player)),
+        }
+    }
+
+    /// Length of the longest continuous road of the player. Roads may not be
+    /// walked twice, and the road can't go through a junction settled by an
+    /// opponent, although it may end there.
+    pub fn longest_road(&self, player: PlayerID) -> usize {
+        let mut walked = vec![false; self.road.settle_places.len()];
+        self.road
+            .owner
+            .iter()
+            .filter(|(_, owner)| **owner == Some(player))
+            .flat_map(|(road, _)| self.road.settle_places[road])
+            .map(|start| self.longest_trail(player, start, true, &mut walked))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn longest_trail(
+        &self,
+        player: PlayerID,
+        at: SettlePlaceID,
+        is_start: bool,
+        walked: &mut [bool],
+    ) -> usize {
+        let blocked = matches!(
+            self.settle_place.occupancy[at],
+            SettlePlace::Settlement(settler) | SettlePlace::Town(settler) if settler != player
+        );
+        if blocked && !is_start {
+            return 0;
+        }
+
+        let mut longest = 0;
+        for road in &self.settle_place.roads[at] {
+            let idx = usize::from(*road);
+            if walked[idx] || self.road.owner[*road] != Some(player) {
+                continue;
+            }
+            let [a, b] = self.road.settle_places[*road];
+            let next = if a == at { b } else { a };
+            walked[idx] = true;
+            longest = longest.max(1 + self.longest_trail(player, next, false, walked));
+            walked[idx] = false;
+        }
+        longest
+    }
+}
+
+impl GameState {
+    /// Empty settle places the player could settle: connected to the
+    /// player's roads, and not adjacent to any other settled place
+    pub fn buildable_settle_places(&self, player: PlayerID) -> Vec<SettlePlaceID> {
+        self.settle_place
+            .roads
+            .iter()
+            .filter(|(settle_place, roads)| {
+                self.settle_place.occupancy[*settle_place] == SettlePlace::Empty
+                    && roads.as_ref().iter().any(|road| self.road.owner[*road] == Some(player))
+                    && self.board().adjacent_settle_places(*settle_place).all(|adjacent| {
+                        self.settle_place.occupancy[adjacent] == SettlePlace::Empty
+                    })
+            })
+            .map(|(settle_place, _)| settle_place)
+            .collect()
+    }
+
+    /// Roads nobody has placed yet, which are connected to the player's network
+    pub fn buildable_roads(&self, player: PlayerID) -> Vec<RoadID> {
+        self.road
+            .owner
+            .iter()
+            .filter(|(road, owner)| owner.is_none() && self.is_connected(player, *road))
+            .map(|(road, _)| road)
+            .collect()
+    }
+}
+
+/// Buildable spots of every player, computed on the first query and kept
+/// until the pieces on the board change. Meant to be queried every frame
+/// by the UI.
+///
+/// The cache does not look at the state to find out whether it changed.
+/// Just like with the [`OccupancyIndex`], it has to be told about every
+/// piece placed, or [`BuildableCache::invalidate`]d when the pieces change
+/// in some other way (e.g. the state is loaded).
+#[derive(Debug, Clone, Default)]
+pub struct BuildableCache {
+    settle_places: Vec<Option<Vec<SettlePlaceID>>>,
+    roads: Vec<Option<Vec<RoadID>>>,
+}
+
+impl BuildableCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cached [`GameState::buildable_settle_places`], or None if the player
+    /// does not play the game
+    pub fn settle_places(
+        &mut self,
+        state: &GameState,
+        player: PlayerID,
+    ) -> Option<&[SettlePlaceID]> {
+        self.fit(state);
+        let cached = self.settle_places.get_mut(usize::from(player))?;
+        Some(cached.get_or_insert_with(|| state.buildable_settle_places(player)))
+    }
+
+    /// Cached [`GameState::buildable_roads`], or None if the player does not
+    /// play the game
+    pub fn roads(&mut self, state: &GameState, player: PlayerID) -> Option<&[RoadID]> {
+        self.fit(state);
+        let cached = self.roads.get_mut(usize::from(player))?;
+        Some(cached.get_or_insert_with(|| state.buildable_roads(player)))
+    }
+
+    /// Record the road placed by the player. The road is no longer buildable
+    /// by anyone, and the spots of the player are computed anew.
+    pub fn place_road(&mut self, road: RoadID, player: PlayerID) {
+        for roads in self.roads.iter_mut().flatten() {
+            roads.retain(|buildable| *buildable != road);
+        }
+        if let Some(roads) = self.roads.get_mut(usize::from(player)) {
+            *roads = None;
+        }
+        if let Some(settle_places) = self.settle_places.get_mut(usize::from(player)) {
+            *settle_places = None;
+        }
+    }
+
+    /// Record the settlement (or the town) built by the player. Settling
+    /// blocks the neighbors for everyone, and may cut the road networks of
+    /// the opponents, so every spot is computed anew.
+    pub fn settle(&mut self, _settle_place: SettlePlaceID, _player: PlayerID) {
+        self.invalidate();
+    }
+
+    /// Forget every computed spot
+    pub fn invalidate(&mut self) {
+        self.settle_places.fill(None);
+        self.roads.fill(None);
+    }
+
+    /// Make room for every player of the game
+    fn fit(&mut self, state: &GameState) {
+        let players = state.player_count as usize;
+        if self.roads.len() != players {
+            self.settle_places = vec![None; players];
+            self.roads = vec![None; players];
+        }
+    }
+}
+
+/// Bitsets of the free spots of the board and of the spots of each of the
+/// players. Kept up to date alongside the relations, by telling the index
+/// about every piece placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OccupancyIndex {
+    free_settle_places: BitSet<SettlePlaceID>,
+    free_roads: BitSet<RoadID>,
+    /// Settle places with either a settlement or a town of the player
+    settled: PlayerRelations<BitSet<SettlePlaceID>>,
+    roads: PlayerRelations<BitSet<RoadID>>,
+}
+
+impl OccupancyIndex {
+    pub fn new(state: &GameState) -> Self {
+        let settle_places = state.settle_place.occupancy.len();
+        let roads = state.road.owner.len();
+        let players = state.player_count as usize;
+        let mut index = Self {
+            free_settle_places: BitSet::full(settle_places),
+            free_roads: BitSet::full(roads),
+            settled: PlayerRelations::from_vec(vec![BitSet::empty(settle_places); players]),
+            roads: PlayerRelations::from_vec(vec![BitSet::empty(roads); players]),
+        };
+        for (road, owner) in &state.road.owner {
+            if let Some(player) = owner {
+                index.place_road(road, *player);
+            }
+        }
+        for (settle_place, occupancy) in &state.settle_place.occupancy {
+            if let SettlePlace::Settlement(player) | SettlePlace::Town(player) = occupancy {
+                index.settle(settle_place, *player);
+            }
+        }
+        index
+    }
+
+    pub fn is_settle_place_free(&self, settle_place: SettlePlaceID) -> bool {
+        self.free_settle_places.contains(settle_place)
+    }
+
+    pub fn is_road_free(&self, road: RoadID) -> bool {
+        self.free_roads.contains(road)
+    }
+
+    /// Settle places with either a settlement or a town of the player
+    pub fn settled_by(&self, player: PlayerID) -> &BitSet<SettlePlaceID> {
+        &self.settled[player]
+    }
+
+    pub fn roads_of(&self, player: PlayerID) -> &BitSet<RoadID> {
+        &self.roads[player]
+    }
+
+    /// Record the road placed by the player
+    pub fn place_road(&mut self, road: RoadID, player: PlayerID) {
+        self.free_roads.remove(road);
+        self.roads[player].insert(road);
+    }
+
+    /// Record the settlement (or the town) built by the player
+    pub fn settle(&mut self, settle_place: SettlePlaceID, player: PlayerID) {
+        self.free_settle_places.remove(settle_place);
+        self.settled[player].insert(settle_place);
+    }
+
+    /// Same as [`GameState::buildable_settle_places`], but only looks at the
+    /// settle places at the ends of the player's roads
+    pub fn buildable_settle_places(
+        &self,
+        state: &GameState,
+        player: PlayerID,
+    ) -> Vec<SettlePlaceID> {
+        let mut candidates = BitSet::empty(self.free_settle_places.capacity());
+        for road in self.roads[player].iter() {
+            for end in state.road.settle_places[road] {
+                candidates.insert(end);
+            }
+        }
+        candidates.intersect_with(&self.free_settle_places);
+        let board = state.board();
+        candidates
+            .iter()
+            .filter(|candidate| {
+                board
+                    .adjacent_settle_places(*candidate)
+                    .all(|adjacent| self.free_settle_places.contains(adjacent))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::{PlayerID, RoadID, SettlePlaceID},
+        relations::GameState,
+        types::SettlePlace,
+        MapConfig,
+    };
+
+    use super::{BuildableCache, OccupancyIndex};
+
+    fn game() -> GameState {
+        let config = MapConfig::standard();
+        decode_config(config, 2).unwrap()
+    }
+
+    /// Walk the board from the settle place, never visiting the same settle
+    /// place twice, returning the roads walked and the settle places visited
+    fn walk(
+        state: &GameState,
+        start: SettlePlaceID,
+        length: usize,
+    ) -> (Vec<RoadID>, Vec<SettlePlaceID>) {
+        let board = state.board();
+        let mut roads = vec![];
+        let mut settle_places = vec![start];
+        for _ in 0..length {
+            let at = *settle_places.last().unwrap();
+            let (road, next) = board
+                .roads_of(at)
+                .iter()
+                .map(|road| {
+                    let [a, b] = board.ends_of(*road);
+                    (*road, if a == at { b } else { a })
+                })
+                .find(|(_, next)| !settle_places.contains(next))
+                .unwrap();
+            roads.push(road);
+            settle_places.push(next);
+        }
+        (roads, settle_places)
+    }
+
+    #[test]
+    fn connectivity_is_broken_by_opponents() {
+        let mut state = game();
+        let (roads, settle_places) = walk(&state, SettlePlaceID(0), 3);
+        let player = PlayerID(0);
+
+        assert!(!state.is_connected(player, roads[0]));
+        state.settle_place.occupancy[settle_places[0]] = SettlePlace::Settlement(player);
+        assert!(state.is_connected(player, roads[0]));
+        assert!(!state.is_connected(player, roads[1]));
+
+        state.road.owner[roads[0]] = Some(player);
+        assert!(state.is_connected(player, roads[1]));
+        assert!(!state.is_connected(PlayerID(1), roads[1]));
+
+        // Opponent settling in between the roads cuts the network
+        state.settle_place.occupancy[settle_places[1]] = SettlePlace::Town(PlayerID(1));
+        assert!(!state.is_connected(player, roads[1]));
+        assert!(state.is_connected(PlayerID(1), roads[1]));
+    }
+
+    #[test]
+    fn longest_road_is_interrupted_by_opponents() {
+        let mut state = game();
+        let (roads, settle_places) = walk(&state, SettlePlaceID(0), 6);
+        let player = PlayerID(0);
+        assert_eq!(state.longest_road(player), 0);
+
+        for road in &roads {
+            state.road.owner[*road] = Some(player);
+        }
+        assert_eq!(state.longest_road(player), 6);
+        assert_eq!(state.longest_road(PlayerID(1)), 0);
+
+        // Own settlements don't break the road
+        state.settle_place.occupancy[settle_places[2]] = SettlePlace::Settlement(player);
+        assert_eq!(state.longest_road(player), 6);
+
+        state.settle_place.occupancy[settle_places[4]] = SettlePlace::Settlement(PlayerID(1));
+        assert_eq!(state.longest_road(player), 4);
+    }
+
+    #[test]
+    fn longest_road_of_a_branching_network() {
+        let mut state = game();
+        let (trunk, settle_places) = walk(&state, SettlePlaceID(0), 4);
+        let player = PlayerID(0);
+        for road in &trunk {
+            state.road.owner[*road] = Some(player);
+        }
+        // A single road branching off the middle of the trunk
+        let branch = *state
+            .board()
+            .roads_of(settle_places[2])
+            .iter()
+            .find(|road| !trunk.contains(road))
+            .unwrap();
+        state.road.owner[branch] = Some(player);
+
+        assert_eq!(state.longest_road(player), 4);
+    }
+
+    #[test]
+    fn buildable_spots() {
+        let mut state = game();
+        let (roads, settle_places) = walk(&state, SettlePlaceID(0), 3);
+        let player = PlayerID(0);
+        assert_eq!(state.buildable_roads(player), vec![]);
+        assert_eq!(state.buildable_settle_places(player), vec![]);
+
+        state.settle_place.occupancy[settle_places[0]] = SettlePlace::Settlement(player);
+        let buildable = state.buildable_roads(player);
+        assert_eq!(buildable.as_slice(), state.board().roads_of(settle_places[0]));
+        // Too close to the settlement
+        assert_eq!(state.buildable_settle_places(player), vec![]);
+
+        for road in &roads {
+            state.road.owner[*road] = Some(player);
+        }
+        let buildable = state.buildable_settle_places(player);
+        assert!(buildable.contains(&settle_places[2]));
+        assert!(buildable.contains(&settle_places[3]));
+        assert!(!buildable.contains(&settle_places[1]));
+        assert!(!state.buildable_roads(player).iter().any(|road| roads.contains(road)));
+
+        state.settle_place.occupancy[settle_places[3]] = SettlePlace::Settlement(PlayerID(1));
+        assert!(!state.buildable_settle_places(player).contains(&settle_places[2]));
+    }
+
+    #[test]
+    fn cache_is_invalidated_by_pieces_only() {
+        let mut state = game();
+        let mut cache = BuildableCache::new();
+        let player = PlayerID(1);
+        assert_eq!(cache.roads(&state, player), Some(&[][..]));
+
+        state.player.hand[player].roads -= 1;
+        assert_eq!(cache.roads(&state, player), Some(&[][..]));
+
+        state.settle_place.occupancy[SettlePlaceID(7)] = SettlePlace::Settlement(player);
+        cache.settle(SettlePlaceID(7), player);
+        let roads = state.buildable_roads(player);
+        assert!(!roads.is_empty());
+        assert_eq!(cache.roads(&state, player), Some(roads.as_slice()));
+        assert_eq!(cache.settle_places(&state, PlayerID(0)), Some(&[][..]));
+
+        // The road is taken away from the opponent without recomputing
+        state.settle_place.occupancy[SettlePlaceID(8)] = SettlePlace::Settlement(PlayerID(0));
+        cache.settle(SettlePlaceID(8), PlayerID(0));
+        let contested = *cache
+            .roads(&state, PlayerID(0))
+            .unwrap()
+            .iter()
+            .find(|road| roads.contains(road))
+            .unwrap();
+        state.road.owner[contested] = Some(player);
+        cache.place_road(contested, player);
+        assert_eq!(
+            cache.roads(&state, PlayerID(0)),
+            Some(state.buildable_roads(PlayerID(0)).as_slice())
+        );
+        assert_eq!(cache.roads(&state, player), Some(state.buildable_roads(player).as_slice()));
+        assert_eq!(
+            cache.settle_places(&state, player),
+            Some(state.buildable_settle_places(player).as_slice())
+        );
+
+        assert_eq!(cache.roads(&state, PlayerID(2)), None);
+        assert_eq!(cache.settle_places(&state, PlayerID(2)), None);
+    }
+
+    #[test]
+    fn occupancy_index_follows_the_pieces() {
+        let mut state = game();
+        let mut index = OccupancyIndex::new(&state);
+        let (roads, settle_places) = walk(&state, SettlePlaceID(0), 3);
+        let player = PlayerID(0);
+        assert!(index.is_road_free(roads[0]));
+
+        state.settle_place.occupancy[settle_places[0]] = SettlePlace::Settlement(player);
+        index.settle(settle_places[0], player);
+        for road in &roads {
+            state.road.owner[*road] = Some(player);
+            index.place_road(*road, player);
+        }
+        state.settle_place.occupancy[SettlePlaceID(30)] = SettlePlace::Town(PlayerID(1));
+        index.settle(SettlePlaceID(30), PlayerID(1));
+
+        assert_eq!(index, OccupancyIndex::new(&state));
+        assert!(!index.is_road_free(roads[0]));
+        assert!(!index.is_settle_place_free(settle_places[0]));
+        assert_eq!(index.roads_of(player).len(), 3);
+        assert!(index.settled_by(PlayerID(1)).contains(SettlePlaceID(30)));
+        assert_eq!(
+            index.buildable_settle_places(&state, player),
+            state.buildable_settle_places(player)
+        );
+    }
+}
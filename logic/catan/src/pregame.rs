@@ -0,0 +1,315 @@
+use crate::{
+    decode_config_with_rules,
+    ids::{PlayerID, PlayerInt},
+    relations::{GameState, PlayerRelations},
+    rules::GameRules,
+    types::{PlayerColor, PlayerProfile},
+    DecodeConfigError, MapConfig, MAX_PLAYERS, MIN_PLAYERS,
+};
+
+/// Amount of the seats in the lobby, one for each of the players the game
+/// can be played by
+pub const SEATS: usize = MAX_PLAYERS as usize;
+
+/// Players gathering for the game before it starts. Users are identified by
+/// whatever the server uses to tell them apart (`U`), and get their
+/// [`PlayerID`]s only once the game is started.
+///
+/// Any change to the seats or to the rules makes everyone not ready, so no
+/// one ends up in the game they didn't agree to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lobby<U> {
+    host: U,
+    rules: GameRules,
+    seats: [Option<Seat<U>>; SEATS],
+}
+
+/// User sitting at the seat of the lobby
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seat<U> {
+    pub user: U,
+    /// Color the user picked. Users who didn't pick one get one of the
+    /// remaining colors when the game starts.
+    pub color: Option<PlayerColor>,
+    pub ready: bool,
+}
+
+/// Reasons the lobby change was rejected. Rejected changes never change the lobby.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum LobbyError {
+    #[error("all of the seats are taken")]
+    Full,
+    #[error("user is already seated")]
+    AlreadySeated,
+    #[error("user is not seated")]
+    NotSeated,
+    #[error("seat {0} does not exist or is taken")]
+    SeatUnavailable(usize),
+    #[error("{0:?} is picked by another player")]
+    ColorTaken(PlayerColor),
+    /// Only the host may change the rules and start the game
+    #[error("only the host can do that")]
+    NotHost,
+    #[error("{0} players can't play the game")]
+    InvalidPlayerCount(usize),
+    /// The seat is occupied by the user who is not ready yet
+    #[error("player at seat {0} is not ready")]
+    NotReady(usize),
+}
+
+/// The players of the started game. Players get their IDs in the order of
+/// the seats they took.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameSetup<U> {
+    pub players: PlayerRelations<(U, PlayerColor)>,
+    pub rules: GameRules,
+}
+
+impl<U> GameSetup<U> {
+    /// Amount of players to pass to [`crate::decode_config_with_rules`]
+    pub fn player_count(&self) -> PlayerInt {
+        self.players.len() as PlayerInt
+    }
+
+    /// Profiles of the players, with the colors they picked in the lobby.
+    /// Names are the placeholder ones (see [`PlayerProfile::placeholder`])
+    /// until the players set their own.
+    pub fn profiles(&self) -> PlayerRelations<PlayerProfile> {
+        PlayerRelations::from_vec(
+            self.players
+                .iter()
+                .map(|(player, (_, color))| PlayerProfile {
+                    color: *color,
+                    ..PlayerProfile::placeholder(player)
+                })
+                .collect(),
+        )
+    }
+
+    /// Start the game of the setup on the map: decode the config for the
+    /// players by the rules of the lobby, and seed their profiles
+    pub fn decode(&self, config: MapConfig) -> Result<GameState, DecodeConfigError> {
+        let mut state = decode_config_with_rules(config, self.player_count(), &self.rules)?;
+        state.player.profile = self.profiles();
+        Ok(state)
+    }
+}
+
+impl<U: PartialEq + Clone> Lobby<U> {
+    /// Empty lobby with the base game rules
+    pub fn new(host: U) -> Self {
+        Self {
+            host,
+            rules: GameRules::default(),
+            seats: Default::default(),
+        }
+    }
+
+    pub fn host(&self) -> &U {
+        &self.host
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    pub fn seats(&self) -> &[Option<Seat<U>>] {
+        &self.seats
+    }
+
+    /// Seat the user at the first free seat, returning its index
+    pub fn join(&mut self, user: U) -> Result<usize, LobbyError> {
+        let seat = self.seats.iter().position(Option::is_none).ok_or(LobbyError::Full)?;
+        self.take_seat(user, seat)?;
+        Ok(seat)
+    }
+
+    /// Seat the user at the given seat
+    pub fn take_seat(&mut self, user: U, seat: usize) -> Result<(), LobbyError> {
+        if self.seat_of(&user).is_some() {
+            return Err(LobbyError::AlreadySeated);
+        }
+        match self.seats.get_mut(seat) {
+            Some(free @ None) => *free = Some(Seat { user, color: None, ready: false }),
+            _ => return Err(LobbyError::SeatUnavailable(seat)),
+        }
+        self.unready_everyone();
+        Ok(())
+    }
+
+    pub fn leave(&mut self, user: &U) -> Result<(), LobbyError> {
+        let seat = self.seat_of(user).ok_or(LobbyError::NotSeated)?;
+        self.seats[seat] = None;
+        self.unready_everyone();
+        Ok(())
+    }
+
+    /// Pick the color for the user, or drop the pick with None
+    pub fn select_color(&mut self, user: &U, color: Option<PlayerColor>) -> Result<(), LobbyError> {
+        let seat = self.seat_of(user).ok_or(LobbyError::NotSeated)?;
+        if let Some(color) = color {
+            let taken = self.occupied().any(|(other, occupant)| {
+                other != seat && occupant.color == Some(color)
+            });
+            if taken {
+                return Err(LobbyError::ColorTaken(color));
+            }
+        }
+        self.seats[seat].as_mut().unwrap().color = color;
+        Ok(())
+    }
+
+    pub fn set_ready(&mut self, user: &U, ready: bool) -> Result<(), LobbyError> {
+        let seat = self.seat_of(user).ok_or(LobbyError::NotSeated)?;
+        self.seats[seat].as_mut().unwrap().ready = ready;
+        Ok(())
+    }
+
+    /// Change the rules of the game. Only the host may do that.
+    pub fn set_rules(&mut self, by: &U, rules: GameRules) -> Result<(), LobbyError> {
+        if *by != self.host {
+            return Err(LobbyError::NotHost);
+        }
+        self.rules = rules;
+        self.unready_everyone();
+        Ok(())
+    }
+
+    /// Start the game, assigning the player IDs and colors. Only the host
+    /// may start the game, and only once all of the seated users are ready.
+    pub fn start(&self, by: &U) -> Result<GameSetup<U>, LobbyError> {
+        if *by != self.host {
+            return Err(LobbyError::NotHost);
+        }
+        let count = self.occupied().count();
+        if !(usize::from(MIN_PLAYERS)..=SEATS).contains(&count) {
+            return Err(LobbyError::InvalidPlayerCount(count));
+        }
+        if let Some((seat, _)) = self.occupied().find(|(_, occupant)| !occupant.ready) {
+            return Err(LobbyError::NotReady(seat));
+        }
+
+        let mut free_colors = PlayerColor::ALL
+            .into_iter()
+            .filter(|color| !self.occupied().any(|(_, occupant)| occupant.color == Some(*color)));
+        let players = self
+            .occupied()
+            .map(|(_, occupant)| {
+                let color = occupant
+                    .color
+                    .or_else(|| free_colors.next())
+                    .expect("there are as many colors as there are seats");
+                (occupant.user.clone(), color)
+            })
+            .collect();
+        Ok(GameSetup {
+            players: PlayerRelations::from_vec(players),
+            rules: self.rules.clone(),
+        })
+    }
+
+    /// Index of the seat the user is sitting at
+    pub fn seat_of(&self, user: &U) -> Option<usize> {
+        self.occupied().find(|(_, occupant)| occupant.user == *user).map(|(seat, _)| seat)
+    }
+
+    /// ID the user will get once the game is started
+    pub fn player_id_of(&self, user: &U) -> Option<PlayerID> {
+        let seat = self.seat_of(user)?;
+        let id = self.seats[..seat].iter().filter(|seat| seat.is_some()).count();
+        Some(PlayerID(id as _))
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = (usize, &Seat<U>)> {
+        self.seats
+            .iter()
+            .enumerate()
+            .filter_map(|(seat, occupant)| Some((seat, occupant.as_ref()?)))
+    }
+
+    fn unready_everyone(&mut self) {
+        for occupant in self.seats.iter_mut().flatten() {
+            occupant.ready = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ids::PlayerID, rules::GameRules, types::PlayerColor, MapConfig};
+
+    use super::{Lobby, LobbyError};
+
+    fn ready_all(lobby: &mut Lobby<&'static str>, users: &[&'static str]) {
+        for user in users {
+            lobby.set_ready(user, true).unwrap();
+        }
+    }
+
+    #[test]
+    fn seats_and_colors() {
+        let mut lobby = Lobby::new("host");
+        assert_eq!(lobby.join("alice"), Ok(0));
+        lobby.take_seat("bob", 2).unwrap();
+        assert_eq!(lobby.take_seat("carol", 2), Err(LobbyError::SeatUnavailable(2)));
+        assert_eq!(lobby.take_seat("carol", 9), Err(LobbyError::SeatUnavailable(9)));
+        assert_eq!(lobby.join("alice"), Err(LobbyError::AlreadySeated));
+        assert_eq!(lobby.join("carol"), Ok(1));
+        assert_eq!(lobby.join("dave"), Ok(3));
+        assert_eq!(lobby.join("erin"), Err(LobbyError::Full));
+
+        lobby.select_color(&"alice", Some(PlayerColor::Blue)).unwrap();
+        assert_eq!(
+            lobby.select_color(&"bob", Some(PlayerColor::Blue)),
+            Err(LobbyError::ColorTaken(PlayerColor::Blue))
+        );
+        lobby.select_color(&"alice", None).unwrap();
+        lobby.select_color(&"bob", Some(PlayerColor::Blue)).unwrap();
+
+        lobby.leave(&"carol").unwrap();
+        assert_eq!(lobby.leave(&"carol"), Err(LobbyError::NotSeated));
+        assert_eq!(lobby.player_id_of(&"bob"), Some(PlayerID(1)));
+        assert_eq!(lobby.player_id_of(&"dave"), Some(PlayerID(2)));
+    }
+
+    #[test]
+    fn starting_the_game() {
+        let mut lobby = Lobby::new("host");
+        lobby.join("alice").unwrap();
+        assert_eq!(lobby.start(&"host"), Err(LobbyError::InvalidPlayerCount(1)));
+        lobby.join("bob").unwrap();
+        lobby.join("carol").unwrap();
+        lobby.select_color(&"bob", Some(PlayerColor::Red)).unwrap();
+        ready_all(&mut lobby, &["alice", "bob", "carol"]);
+
+        // Changing the rules needs everyone to agree again
+        let rules = GameRules {
+            shuffle_seed: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(lobby.set_rules(&"alice", rules.clone()), Err(LobbyError::NotHost));
+        lobby.set_rules(&"host", rules.clone()).unwrap();
+        assert_eq!(lobby.start(&"host"), Err(LobbyError::NotReady(0)));
+        ready_all(&mut lobby, &["alice", "bob", "carol"]);
+        assert_eq!(lobby.start(&"alice"), Err(LobbyError::NotHost));
+
+        let setup = lobby.start(&"host").unwrap();
+        assert_eq!(setup.rules, rules);
+        assert_eq!(
+            setup.players.values().cloned().collect::<Vec<_>>(),
+            [
+                ("alice", PlayerColor::Blue),
+                ("bob", PlayerColor::Red),
+                ("carol", PlayerColor::White),
+            ]
+        );
+
+        let state = setup.decode(MapConfig::standard()).unwrap();
+        assert_eq!(state.player_count, setup.player_count());
+        assert_eq!(state.player.hand.len(), 3);
+        let colors: Vec<_> = state.player.profile.values().map(|profile| profile.color).collect();
+        assert_eq!(colors, [PlayerColor::Blue, PlayerColor::Red, PlayerColor::White]);
+        assert_eq!(state.player.profile[PlayerID(1)].name, "Player 2");
+    }
+}
@@ -3,68 +3,185 @@ use enum_map::EnumMap;
 use crate::{
     adjacency_list::AdjacencyList,
     array_vec::ArrayVec,
-    ids::{DiceMarkerID, ResourceTileID, RoadID, SettlePlaceID, TileID, PlayerID},
-    types::{DiceMarker, HexSide, HexVertex, PlayerHand, TileTerrain},
+    grid::Grid,
+    ids::{
+        CoordInt, DiceMarkerID, ResourceTileID, RoadID, SettlePlaceID, TileID, PlayerID, PlayerInt,
+    },
+    types::{DiceMarker, Harbour, HexSide, HexVertex, PlayerHand, PlayerProfile, SettlePlace, TileTerrain},
 };
 
+/// Implement Clone for the entities field-by-field, so that `clone_from`
+/// reuses every allocation of the relations being overwritten. Derived
+/// Clone would allocate the state anew, which is too slow for the bots
+/// cloning the state for every rollout.
+macro_rules! clone_fields {
+    ($name: ident { $($field: ident),* $(,)? }) => {
+        impl Clone for $name {
+            fn clone(&self) -> Self {
+                Self {
+                    $($field: self.$field.clone()),*
+                }
+            }
+
+            fn clone_from(&mut self, source: &Self) {
+                $(self.$field.clone_from(&source.$field);)*
+            }
+        }
+    };
+}
+
 pub type TileRelations<T> = AdjacencyList<TileID, T>;
 
 /// All of the properties of ALL Tile entities stored as a set of
 /// relationships to all other entities.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct TileEntities {
     pub resource: TileRelations<TileTerrain>,
+    /// Offset coordinates of the tile, as specified in the map config
+    pub position: TileRelations<[CoordInt; 2]>,
     pub roads: TileRelations<EnumMap<HexSide, RoadID>>,
     pub settle_places: TileRelations<EnumMap<HexVertex, SettlePlaceID>>,
+    /// Tiles laying next to each of the sides of the tile, if there are any
+    pub neighbors: TileRelations<EnumMap<HexSide, Option<TileID>>>,
+    /// Dice marker laid on top of the tile. Deserts have none.
+    pub dice_marker: TileRelations<Option<DiceMarker>>,
+    /// ID of the tile among the tiles producing resources. Deserts have none.
+    pub resource_tile: TileRelations<Option<ResourceTileID>>,
+    /// The tile at each of the offset coordinates of the map, if there is any
+    pub grid: Grid<Option<TileID>>,
 }
 
 pub type RoadRelations<T> = AdjacencyList<RoadID, T>;
 
 /// All of the properties of ALL Road entities stored as a set of
 /// relationships to all other entities.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct RoadEntities {
     pub settle_places: RoadRelations<[SettlePlaceID; 2]>,
+    /// The player which has placed the road, if any
+    pub owner: RoadRelations<Option<PlayerID>>,
 }
 
 pub type PlayerRelations<T> = AdjacencyList<PlayerID, T>;
 
 /// All of the properties of ALL Player entities stored as a set of
 /// relationships to all other entities.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct PlayerEntities {
     pub placed_roads: PlayerRelations<Vec<RoadID>>,
     pub towns: PlayerRelations<Vec<SettlePlaceID>>,
     pub settlements: PlayerRelations<Vec<SettlePlaceID>>,
     pub hand: PlayerRelations<PlayerHand>,
+    pub profile: PlayerRelations<PlayerProfile>,
 }
 
 pub type SettleRelations<T> = AdjacencyList<SettlePlaceID, T>;
 
 /// All of the properties of ALL SettlePlaces entities stored as a set of
 /// relationships to all other entities.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct SettlePlaceEntities {
     pub roads: SettleRelations<ArrayVec<RoadID, 3>>,
-    // pub tiles: CappedAdjacencyList<TileID, 2, 3>
+    /// Tiles the settle place is a vertex of
+    pub tiles: SettleRelations<ArrayVec<TileID, 3>>,
+    /// What is built on the settle place
+    pub occupancy: SettleRelations<SettlePlace>,
+}
+
+pub type ResourceTileRelations<T> = AdjacencyList<ResourceTileID, T>;
+
+/// All of the properties of ALL resource tiles (the tiles other than
+/// deserts) stored as a set of relationships to all other entities.
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
+pub struct ResourceTileEntities {
+    /// The tile of the board, which is the resource tile
+    pub tile: ResourceTileRelations<TileID>,
 }
 
 pub type DiceMarkerRelations<T> = AdjacencyList<DiceMarkerID, T>;
 
 /// All of the properties of ALL DiceMarker entities stored as a set of
 /// relationships to all other entities.
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct DiceMarkerEntities {
     pub values: DiceMarkerRelations<DiceMarker>,
     pub place: DiceMarkerRelations<ResourceTileID>,
 }
 
+/// All of the properties of ALL harbours, in the order of the harbour
+/// placement of the map config.
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
+pub struct HarbourEntities {
+    /// Settle places at the ends of the tile side the harbour faces
+    pub settle_places: Vec<[SettlePlaceID; 2]>,
+    /// What the harbour trades, as specified in the map config
+    pub kind: Vec<Harbour>,
+}
+
 /// The current state of the game, containing all of the relationships
 /// between game objects and players
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct GameState {
+    pub player_count: PlayerInt,
     pub tile: TileEntities,
     pub road: RoadEntities,
     pub player: PlayerEntities,
     pub settle_place: SettlePlaceEntities,
+    pub resource_tile: ResourceTileEntities,
+    pub dice_marker: DiceMarkerEntities,
+    pub harbour: HarbourEntities,
+    /// The tile the robber is on, or None while it is off the board
+    pub robber: Option<TileID>,
+}
+
+clone_fields!(TileEntities {
+    resource,
+    position,
+    roads,
+    settle_places,
+    neighbors,
+    dice_marker,
+    resource_tile,
+    grid,
+});
+clone_fields!(RoadEntities { settle_places, owner });
+clone_fields!(PlayerEntities { placed_roads, towns, settlements, hand, profile });
+clone_fields!(SettlePlaceEntities { roads, tiles, occupancy });
+clone_fields!(ResourceTileEntities { tile });
+clone_fields!(DiceMarkerEntities { values, place });
+clone_fields!(HarbourEntities { settle_places, kind });
+clone_fields!(GameState {
+    player_count,
+    tile,
+    road,
+    player,
+    settle_place,
+    resource_tile,
+    dice_marker,
+    harbour,
+    robber,
+});
+
+#[cfg(test)]
+mod test {
+    use crate::{decode_config, ids::{PlayerID, RoadID}, MapConfig};
+
+    #[test]
+    fn clone_from_reuses_allocations() {
+        let config = MapConfig::standard();
+        let mut state = decode_config(config, 2).unwrap();
+        state.player.placed_roads[PlayerID(0)] = vec![RoadID(0), RoadID(1)];
+
+        let mut rollout = state.clone();
+        assert_eq!(rollout, state);
+        rollout.player.placed_roads[PlayerID(0)].push(RoadID(2));
+        assert_ne!(rollout, state);
+
+        let roads = rollout.player.placed_roads[PlayerID(0)].as_ptr();
+        let positions = rollout.tile.position.values().as_slice().as_ptr();
+        rollout.clone_from(&state);
+        assert_eq!(rollout, state);
+        assert_eq!(rollout.player.placed_roads[PlayerID(0)].as_ptr(), roads);
+        assert_eq!(rollout.tile.position.values().as_slice().as_ptr(), positions);
+    }
 }
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decode_config_with_rules,
+    delta::{DeltaError, StateDelta},
+    ids::PlayerInt,
+    relations::GameState,
+    rules::GameRules,
+    DecodeConfigError, MapConfig,
+};
+
+/// Recording of the whole game: the map and the rules it was played by
+/// (including the shuffle seed), and the changes of every turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay {
+    pub config: MapConfig,
+    pub rules: GameRules,
+    pub player_count: PlayerInt,
+    pub turns: Vec<ReplayTurn>,
+}
+
+/// Changes made by a single turn of the game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayTurn {
+    /// Milliseconds since the start of the game
+    pub timestamp: u64,
+    /// Delta from the state at the end of the previous turn
+    pub delta: StateDelta,
+}
+
+impl Replay {
+    pub fn new(config: MapConfig, rules: GameRules, player_count: PlayerInt) -> Self {
+        Self {
+            config,
+            rules,
+            player_count,
+            turns: Vec::new(),
+        }
+    }
+
+    /// Record the turn, which changed the state from `before` into `after`
+    pub fn record(&mut self, timestamp: u64, before: &GameState, after: &GameState) {
+        self.turns.push(ReplayTurn {
+            timestamp,
+            delta: before.diff(after),
+        });
+    }
+
+    /// State of the game before the first turn
+    pub fn initial_state(&self) -> Result<GameState, DecodeConfigError> {
+        decode_config_with_rules(self.config.clone(), self.player_count, &self.rules)
+    }
+
+    /// Cursor positioned before the first turn
+    pub fn cursor(&self) -> Result<ReplayCursor<'_>, DecodeConfigError> {
+        let initial = self.initial_state()?;
+        Ok(ReplayCursor {
+            replay: self,
+            state: initial.clone(),
+            initial,
+            turn: 0,
+        })
+    }
+}
+
+/// Position in the replay, along with the state of the game at it
+#[derive(Debug, Clone)]
+pub struct ReplayCursor<'a> {
+    replay: &'a Replay,
+    initial: GameState,
+    state: GameState,
+    /// Amount of turns applied to the state
+    turn: usize,
+}
+
+impl ReplayCursor<'_> {
+    /// State after the [`ReplayCursor::turn`] turns were played
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Amount of turns played up to the cursor
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// Timestamp of the last played turn, or 0 before the first turn
+    pub fn timestamp(&self) -> u64 {
+        match self.turn {
+            0 => 0,
+            turn => self.replay.turns[turn - 1].timestamp,
+        }
+    }
+
+    /// Move to the state after the given amount of turns, or to the end of
+    /// the replay if there are fewer turns. Seeking backwards replays the
+    /// game from the start.
+    ///
+    /// Replays are read from the untrusted files, so the turn may not fit
+    /// the state it is played on. The cursor then stops before that turn.
+    pub fn seek(&mut self, turn: usize) -> Result<(), DeltaError> {
+        let turn = turn.min(self.replay.turns.len());
+        if turn < self.turn {
+            self.state.clone_from(&self.initial);
+            self.turn = 0;
+        }
+        for played in &self.replay.turns[self.turn..turn] {
+            self.state.apply_delta(played.delta.clone())?;
+            self.turn += 1;
+        }
+        Ok(())
+    }
+
+    /// Play the next turn, if there is one
+    pub fn advance(&mut self) -> Result<bool, DeltaError> {
+        let before = self.turn;
+        self.seek(self.turn + 1)?;
+        Ok(self.turn != before)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::{PlayerID, RoadID},
+        relations::GameState,
+        rules::GameRules,
+        MapConfig,
+    };
+
+    use super::{DeltaError, Replay};
+
+    #[test]
+    fn seeking_replays_the_turns() {
+        let config = MapConfig::standard();
+        let mut replay = Replay::new(config.clone(), GameRules::default(), 2);
+
+        let mut states: Vec<GameState> = vec![decode_config(config, 2).unwrap()];
+        for turn in 0..5u8 {
+            let mut next = states.last().unwrap().clone();
+            let player = PlayerID(turn % 2);
+            next.road.owner[RoadID(turn.into())] = Some(player);
+            next.player.hand[player].roads -= 1;
+            replay.record(1000 * turn as u64, states.last().unwrap(), &next);
+            states.push(next);
+        }
+
+        let replay: Replay =
+            serde_json::from_str(&serde_json::to_string(&replay).unwrap()).unwrap();
+        let mut cursor = replay.cursor().unwrap();
+        assert_eq!(cursor.state(), &states[0]);
+        cursor.seek(3).unwrap();
+        assert_eq!(cursor.state(), &states[3]);
+        assert_eq!(cursor.timestamp(), 2000);
+        cursor.seek(1).unwrap();
+        assert_eq!(cursor.state(), &states[1]);
+
+        while cursor.advance().unwrap() {}
+        assert_eq!(cursor.turn(), 5);
+        assert_eq!(cursor.state(), &states[5]);
+        cursor.seek(100).unwrap();
+        assert_eq!(cursor.turn(), 5);
+    }
+
+    #[test]
+    fn malformed_turns_stop_the_cursor() {
+        let config = MapConfig::standard();
+        let mut replay = Replay::new(config.clone(), GameRules::default(), 2);
+        let initial = decode_config(config, 2).unwrap();
+        let mut next = initial.clone();
+        next.road.owner[RoadID(0)] = Some(PlayerID(0));
+        replay.record(0, &initial, &next);
+        replay.record(1000, &next, &next);
+        replay.turns[1].delta.road.owner.changed.push((RoadID(1000), None));
+
+        let mut cursor = replay.cursor().unwrap();
+        assert_eq!(
+            cursor.seek(2),
+            Err(DeltaError::KeyOutOfRange { key: 1000, len: 72 })
+        );
+        assert_eq!(cursor.turn(), 1);
+        assert_eq!(cursor.state(), &next);
+        assert!(cursor.advance().is_err());
+        assert_eq!(cursor.turn(), 1);
+    }
+}
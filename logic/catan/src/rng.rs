@@ -0,0 +1,69 @@
+/// Small seedable pseudo-random number generator (SplitMix64).
+///
+/// The engine only needs the randomness to be reproducible from the seed on
+/// every platform, so that peers which agreed on a seed shuffle and generate
+/// identically. It is not suitable for anything security related.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed integer in `0..bound`. Panics if the bound is 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "empty range");
+        // Reject the values of the last incomplete span to avoid modulo bias
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Shuffle the slice in place (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rng;
+
+    #[test]
+    fn matches_reference_splitmix64() {
+        let mut rng = Rng::from_seed(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+    }
+
+    #[test]
+    fn shuffle_is_a_reproducible_permutation() {
+        let mut values: Vec<u32> = (0..50).collect();
+        Rng::from_seed(7).shuffle(&mut values);
+        let mut again: Vec<u32> = (0..50).collect();
+        Rng::from_seed(7).shuffle(&mut again);
+        assert_eq!(values, again);
+        assert_ne!(values, (0..50).collect::<Vec<_>>());
+
+        values.sort();
+        assert_eq!(values, (0..50).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,203 @@
+use enum_map::{enum_map, EnumMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rng::Rng,
+    types::{DevelopmentCard, PlayerHand},
+};
+
+/// Rules of the game, which are not specific to the map. Defaults to the
+/// rules of the base game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameRules {
+    /// Pieces each player starts the game with
+    pub pieces: PieceBank,
+    /// Amount of each of the development cards in the deck
+    pub development_deck: EnumMap<DevelopmentCard, u8>,
+    /// Seed the development deck is shuffled with. Peers agreeing on the seed
+    /// get the same deck order. When None, the deck is left unshuffled.
+    pub shuffle_seed: Option<u64>,
+    /// Where the robber is at the start of the game
+    #[serde(default)]
+    pub robber_start: RobberStart,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            pieces: PieceBank::default(),
+            development_deck: enum_map! {
+                DevelopmentCard::Knight => 14,
+                DevelopmentCard::RoadBuilding => 2,
+                DevelopmentCard::YearOfPlenty => 2,
+                DevelopmentCard::Monopoly => 2,
+                DevelopmentCard::VictoryPoint => 5,
+            },
+            shuffle_seed: None,
+            robber_start: RobberStart::default(),
+        }
+    }
+}
+
+/// Where the robber is placed at the start of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum RobberStart {
+    /// On the desert. Maps with many deserts specify which one, see
+    /// [`crate::MapConfig::robber_tile`].
+    #[default]
+    Desert,
+    /// Off the board, until the first seven is rolled
+    OffBoard,
+}
+
+/// Amount of pieces of each kind available to a single player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceBank {
+    pub settlements: u8,
+    pub towns: u8,
+    pub roads: u8,
+}
+
+impl Default for PieceBank {
+    /// Pieces of the base game
+    fn default() -> Self {
+        Self {
+            settlements: 5,
+            towns: 4,
+            roads: 15,
+        }
+    }
+}
+
+impl GameRules {
+    /// The hand every player starts the game with: no resources, and all
+    /// of the pieces from the bank left to place
+    pub fn starting_hand(&self) -> PlayerHand {
+        PlayerHand {
+            resources: EnumMap::default(),
+            settlements: self.pieces.settlements,
+            towns: self.pieces.towns,
+            roads: self.pieces.roads,
+        }
+    }
+
+    /// The development deck in the order the cards are drawn, the first card
+    /// being drawn first
+    pub fn development_deck(&self) -> Vec<DevelopmentCard> {
+        let mut deck: Vec<_> = self
+            .development_deck
+            .iter()
+            .flat_map(|(card, amount)| std::iter::repeat_n(card, *amount as usize))
+            .collect();
+        if let Some(seed) = self.shuffle_seed {
+            Rng::from_seed(seed).shuffle(&mut deck);
+        }
+        deck
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use enum_map::enum_map;
+
+    use crate::{
+        decode_config, decode_config_with_rules,
+        ids::PlayerID,
+        ids::TileID,
+        types::{DevelopmentCard, PlayerColor, TileTerrain},
+        MapConfig, MapConfigBuilder, MapConfigError,
+    };
+
+    use super::{GameRules, PieceBank, RobberStart};
+
+    #[test]
+    fn players_start_with_the_piece_bank() {
+        let state = decode_config(MapConfig::standard(), 3).unwrap();
+        assert_eq!(state.player_count, 3);
+        assert_eq!(state.player.hand.len(), 3);
+        assert_eq!(state.player.placed_roads.len(), 3);
+        let hand = state.player.hand[PlayerID(2)];
+        assert_eq!((hand.settlements, hand.towns, hand.roads), (5, 4, 15));
+        assert!(hand.resources.values().all(|amount| *amount == 0));
+        assert_eq!(state.player.profile[PlayerID(2)].name, "Player 3");
+        assert_eq!(state.player.profile[PlayerID(2)].color, PlayerColor::White);
+
+        let rules = GameRules {
+            pieces: PieceBank {
+                settlements: 2,
+                towns: 1,
+                roads: 7,
+            },
+            ..Default::default()
+        };
+        let state = decode_config_with_rules(MapConfig::standard(), 2, &rules).unwrap();
+        assert_eq!(state.player.hand[PlayerID(1)], rules.starting_hand());
+        assert_eq!(state.player.hand[PlayerID(1)].roads, 7);
+    }
+
+    #[test]
+    fn robber_starts_where_the_rules_say() {
+        let state = decode_config(MapConfig::standard(), 2).unwrap();
+        assert_eq!(state.robber, Some(TileID(9)));
+
+        let off_board = GameRules {
+            robber_start: RobberStart::OffBoard,
+            ..Default::default()
+        };
+        let state = decode_config_with_rules(MapConfig::standard(), 2, &off_board).unwrap();
+        assert_eq!(state.robber, None);
+
+        let two_deserts = MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Desert)
+            .add_tile([1, 0], TileTerrain::Field)
+            .add_tile([2, 0], TileTerrain::Desert);
+        let config = two_deserts.clone().build().unwrap();
+        assert_eq!(decode_config(config, 2).unwrap().robber, Some(TileID(0)));
+        let config = two_deserts.clone().set_robber_tile(TileID(2)).build().unwrap();
+        assert_eq!(decode_config(config, 2).unwrap().robber, Some(TileID(2)));
+
+        assert_eq!(
+            two_deserts.clone().set_robber_tile(TileID(1)).build(),
+            Err(MapConfigError::RobberNotOnDesert(TileID(1)))
+        );
+        assert_eq!(
+            two_deserts.set_robber_tile(TileID(3)).build(),
+            Err(MapConfigError::UnknownRobberTile(TileID(3)))
+        );
+
+        let no_deserts = MapConfigBuilder::new().add_tile([0, 0], TileTerrain::Field).build();
+        assert_eq!(decode_config(no_deserts.unwrap(), 2).unwrap().robber, None);
+    }
+
+    #[test]
+    fn development_deck_composition() {
+        let rules = GameRules::default();
+        let deck = rules.development_deck();
+        assert_eq!(deck.len(), 25);
+        assert_eq!(deck.iter().filter(|card| **card == DevelopmentCard::Knight).count(), 14);
+
+        let variant = GameRules {
+            development_deck: enum_map! {
+                DevelopmentCard::Knight => 3,
+                DevelopmentCard::VictoryPoint => 3,
+                _ => 0,
+            },
+            shuffle_seed: Some(42),
+            ..Default::default()
+        };
+        let deck = variant.development_deck();
+        assert_eq!(deck.len(), 6);
+        assert_eq!(deck, variant.development_deck());
+        assert_ne!(
+            deck,
+            GameRules {
+                shuffle_seed: None,
+                ..variant.clone()
+            }
+            .development_deck()
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    decode_config_with_rules,
+    delta::{DeltaError, StateDelta},
+    ids::PlayerInt,
+    relations::GameState,
+    rules::GameRules,
+    topology::{verify_topology, TopologyDiagnostics},
+    DecodeConfigError, MapConfig,
+};
+
+/// Version of the save format written by this version of the crate.
+///
+/// Whenever the layout of the save changes (e.g. a relation is added to the
+/// state), the version is bumped, and the migration of the saves of the
+/// previous version is pushed to [`MIGRATIONS`].
+pub const SAVE_VERSION: u32 = 1;
+
+/// Upgrade of the save from the version to the next one, done on the JSON
+/// value of the save before it is deserialized
+type Migration = fn(&mut Value) -> Result<(), SaveError>;
+
+/// Migrations of the saves of each of the versions, the first one migrating
+/// the saves of version 1 to version 2
+const MIGRATIONS: &[Migration] = &[];
+
+const _: () = assert!(MIGRATIONS.len() + 1 == SAVE_VERSION as usize);
+
+/// Everything needed to resume the game later
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedGame {
+    pub config: MapConfig,
+    pub rules: GameRules,
+    pub state: GameState,
+}
+
+/// Reasons the game could not be saved or loaded
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SaveError {
+    #[error("could not read or write the save")]
+    Io(#[source] std::io::Error),
+    #[error("save is malformed")]
+    Json(#[source] serde_json::Error),
+    /// The version is missing, or it is not any of the versions ever written
+    #[error("save of version {0} is not supported")]
+    UnsupportedVersion(u32),
+    /// The save was written by a newer version of the crate
+    #[error("save of version {found} is newer than the supported version {supported}")]
+    FutureVersion { found: u32, supported: u32 },
+    /// The map of the save could not be decoded
+    #[error("could not decode the map of the save")]
+    Decode(#[source] DecodeConfigError),
+    /// The changes of the save do not fit the state decoded from its map
+    #[error("changes of the save do not fit its map")]
+    Delta(#[source] DeltaError),
+    /// The board of the loaded game is not consistent
+    #[error("loaded board is not a consistent hex board")]
+    Topology(#[source] TopologyDiagnostics),
+}
+
+/// The save as it is written. The board topology is fixed by the config, so
+/// instead of the whole state only its changes since the decoding are stored.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveFile {
+    version: u32,
+    config: MapConfig,
+    rules: GameRules,
+    player_count: PlayerInt,
+    changes: StateDelta,
+}
+
+impl SavedGame {
+    /// Write the game as JSON
+    pub fn save(&self, writer: impl Write) -> Result<(), SaveError> {
+        let initial =
+            decode_config_with_rules(self.config.clone(), self.state.player_count, &self.rules)
+                .map_err(SaveError::Decode)?;
+        let file = SaveFile {
+            version: SAVE_VERSION,
+            config: self.config.clone(),
+            rules: self.rules.clone(),
+            player_count: self.state.player_count,
+            changes: initial.diff(&self.state),
+        };
+        serde_json::to_writer(writer, &file).map_err(SaveError::Json)
+    }
+
+    /// Read the game written by [`SavedGame::save`] of this or any of the
+    /// previous versions of the crate
+    pub fn load(reader: impl Read) -> Result<Self, SaveError> {
+        let mut save: Value = serde_json::from_reader(reader).map_err(|err| match err.is_io() {
+            true => SaveError::Io(err.into()),
+            false => SaveError::Json(err),
+        })?;
+        migrate(&mut save, MIGRATIONS)?;
+        let file: SaveFile = serde_json::from_value(save).map_err(SaveError::Json)?;
+        let mut state =
+            decode_config_with_rules(file.config.clone(), file.player_count, &file.rules)
+                .map_err(SaveError::Decode)?;
+        state.apply_delta(file.changes).map_err(SaveError::Delta)?;
+        verify_topology(&state).map_err(SaveError::Topology)?;
+        Ok(Self {
+            config: file.config,
+            rules: file.rules,
+            state,
+        })
+    }
+}
+
+/// Bring the save up to the latest version, which is the one after the
+/// last of the migrations
+fn migrate(save: &mut Value, migrations: &[Migration]) -> Result<(), SaveError> {
+    let latest = migrations.len() as u32 + 1;
+    let version = save.get("version").and_then(Value::as_u64).unwrap_or(0);
+    let version = u32::try_from(version).unwrap_or(u32::MAX);
+    if version > latest {
+        return Err(SaveError::FutureVersion {
+            found: version,
+            supported: latest,
+        });
+    }
+    if version == 0 {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    for migration in &migrations[version as usize - 1..] {
+        migration(save)?;
+    }
+    save["version"] = latest.into();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use crate::{
+        decode_config_with_rules,
+        delta::DeltaError,
+        ids::{PlayerID, RoadID, SettlePlaceID, TileID},
+        rules::GameRules,
+        types::{Resource, SettlePlace},
+        MapConfig,
+    };
+
+    use super::{migrate, Migration, SaveError, SavedGame, SAVE_VERSION};
+
+    fn game() -> SavedGame {
+        let config = MapConfig::standard();
+        let rules = GameRules {
+            shuffle_seed: Some(3),
+            ..Default::default()
+        };
+        let state = decode_config_with_rules(config.clone(), 3, &rules).unwrap();
+        SavedGame { config, rules, state }
+    }
+
+    #[test]
+    fn games_are_resumed_as_saved() {
+        let mut game = game();
+        let state = &mut game.state;
+        state.settle_place.occupancy[SettlePlaceID(4)] = SettlePlace::Town(PlayerID(2));
+        state.player.towns[PlayerID(2)].push(SettlePlaceID(4));
+        state.road.owner[RoadID(9)] = Some(PlayerID(2));
+        state.player.hand[PlayerID(1)].resources[Resource::Ore] = 4;
+        state.player.profile[PlayerID(0)].name = "Alice".to_owned();
+        state.robber = Some(TileID(3));
+
+        let mut bytes = vec![];
+        game.save(&mut bytes).unwrap();
+        assert_eq!(SavedGame::load(bytes.as_slice()).unwrap(), game);
+    }
+
+    #[test]
+    fn malformed_saves_are_rejected() {
+        assert!(matches!(SavedGame::load(&b"{"[..]), Err(SaveError::Json(_))));
+
+        let mut bytes = vec![];
+        game().save(&mut bytes).unwrap();
+        let mut save: Value = serde_json::from_slice(&bytes).unwrap();
+        save["version"] = (SAVE_VERSION + 1).into();
+        assert!(matches!(
+            SavedGame::load(save.to_string().as_bytes()),
+            Err(SaveError::FutureVersion { found, supported: SAVE_VERSION })
+                if found == SAVE_VERSION + 1
+        ));
+        save.as_object_mut().unwrap().remove("version");
+        assert!(matches!(
+            SavedGame::load(save.to_string().as_bytes()),
+            Err(SaveError::UnsupportedVersion(0))
+        ));
+    }
+
+    #[test]
+    fn tampered_saves_are_rejected() {
+        let mut game = game();
+        game.state.road.owner[RoadID(9)] = Some(PlayerID(2));
+        let mut bytes = vec![];
+        game.save(&mut bytes).unwrap();
+        let save: Value = serde_json::from_slice(&bytes).unwrap();
+        let load = |save: &Value| SavedGame::load(save.to_string().as_bytes());
+
+        let mut tampered = save.clone();
+        tampered["changes"]["road"]["owner"]["len"] = 0.into();
+        assert!(matches!(load(&tampered), Err(SaveError::Delta(_))));
+        tampered["changes"]["road"]["owner"]["changed"] = Value::Array(vec![]);
+        assert!(matches!(
+            load(&tampered),
+            Err(SaveError::Delta(DeltaError::FixedLength { expected: 72, actual: 0 }))
+        ));
+
+        let mut tampered = save.clone();
+        tampered["changes"]["road"]["owner"]["changed"][0][1] = 3.into();
+        assert!(matches!(
+            load(&tampered),
+            Err(SaveError::Delta(DeltaError::UnknownPlayer(player))) if player == PlayerID(3)
+        ));
+
+        // Players of the game can't be added or removed by the changes
+        for relation in ["placed_roads", "towns", "settlements", "hand", "profile"] {
+            let mut tampered = save.clone();
+            tampered["changes"]["player"][relation]["len"] = 0.into();
+            tampered["changes"]["player"][relation]["changed"] = Value::Array(vec![]);
+            assert!(matches!(
+                load(&tampered),
+                Err(SaveError::Delta(DeltaError::FixedLength { expected: 3, actual: 0 }))
+            ));
+        }
+
+        let mut tampered = save.clone();
+        tampered["changes"]["robber"] = 40.into();
+        assert!(matches!(
+            load(&tampered),
+            Err(SaveError::Delta(DeltaError::UnknownTile(tile))) if tile == TileID(40)
+        ));
+
+        assert_eq!(load(&save).unwrap(), game);
+    }
+
+    #[test]
+    fn saves_are_migrated_version_by_version() {
+        fn rename_players(save: &mut Value) -> Result<(), SaveError> {
+            let players = save.as_object_mut().unwrap().remove("players").unwrap();
+            save["playerCount"] = players;
+            Ok(())
+        }
+        fn double_player_count(save: &mut Value) -> Result<(), SaveError> {
+            save["playerCount"] = (save["playerCount"].as_u64().unwrap() * 2).into();
+            Ok(())
+        }
+        let migrations: &[Migration] = &[rename_players, double_player_count];
+
+        let mut save = json!({ "version": 1, "players": 2 });
+        migrate(&mut save, migrations).unwrap();
+        assert_eq!(save, json!({ "version": 3, "playerCount": 4 }));
+
+        let mut save = json!({ "version": 2, "playerCount": 2 });
+        migrate(&mut save, migrations).unwrap();
+        assert_eq!(save, json!({ "version": 3, "playerCount": 4 }));
+
+        let mut save = json!({ "version": 3, "playerCount": 2 });
+        migrate(&mut save, migrations).unwrap();
+        assert_eq!(save["playerCount"], 2);
+    }
+}
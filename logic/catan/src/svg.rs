@@ -0,0 +1,279 @@
+use std::fmt::{Display, Write};
+
+use crate::{
+    layout::{Layout, Point},
+    relations::GameState,
+    types::{Harbour, PlayerColor, SettlePlace, TileTerrain},
+};
+
+/// Render the decoded board as an SVG document, with the given layout.
+///
+/// Every tile is drawn as a hexagon filled with the color of its terrain,
+/// with the number of its dice marker written in the middle. Harbours are
+/// drawn in the water next to the side they serve, labelled with their trade
+/// ratio, and the pieces are drawn in the colors of their owners, with the
+/// robber on top of everything. Every element has the class of what it
+/// depicts (e.g. "tile" or "road"). The view box is fitted to the board with
+/// the padding of half a tile on each side.
+pub fn render_svg(state: &GameState, layout: &Layout) -> String {
+    let board = state.board();
+    let positions = layout.board(state);
+    let tiles: Vec<_> = state
+        .tile
+        .position
+        .iter()
+        .map(|(tile, position)| {
+            (
+                tile,
+                layout.tile_center(*position),
+                layout.tile_corners(*position),
+            )
+        })
+        .collect();
+
+    let padding = layout.size / 2.;
+    let (mut min, mut max) = (
+        Point::new(f64::INFINITY, f64::INFINITY),
+        Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+    );
+    for corner in tiles.iter().flat_map(|(_, _, corners)| corners) {
+        min = Point::new(min.x.min(corner.x), min.y.min(corner.y));
+        max = Point::new(max.x.max(corner.x), max.y.max(corner.y));
+    }
+    if tiles.is_empty() {
+        (min, max) = (Point::default(), Point::default());
+    }
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+        min.x - padding,
+        min.y - padding,
+        max.x - min.x + 2. * padding,
+        max.y - min.y + 2. * padding,
+    )
+    .unwrap();
+
+    let size = layout.size;
+    for (tile, center, corners) in &tiles {
+        let points: Vec<_> = corners
+            .iter()
+            .map(|corner| format!("{:.2},{:.2}", corner.x, corner.y))
+            .collect();
+        writeln!(
+            svg,
+            r##"  <polygon class="tile" points="{}" fill="{}" stroke="#000" stroke-width="{:.2}"/>"##,
+            points.join(" "),
+            terrain_color(state.tile.resource[*tile]),
+            size / 20.,
+        )
+        .unwrap();
+        if let Some(marker) = board.dice_marker(*tile) {
+            circle(&mut svg, "marker", *center, size / 3., "#f5ecd0");
+            // The most likely numbers are conventionally written in red
+            let color = if marker.pips() == 5 { "#c00" } else { "#000" };
+            text(&mut svg, *center, size / 2.5, color, marker.value());
+        }
+    }
+
+    for (kind, [a, b]) in board.harbours() {
+        // Harbours are in the water, on the other side of the tile side they
+        // serve from the tile the side belongs to
+        let side = positions.settle_place(a).midpoint(positions.settle_place(b));
+        let Some(tile) = board.tiles_of(a).iter().find(|tile| board.tiles_of(b).contains(tile))
+        else {
+            continue;
+        };
+        let center = positions.tile(*tile);
+        let harbour = Point::new(
+            side.x + (side.x - center.x) / 3.,
+            side.y + (side.y - center.y) / 3.,
+        );
+        for end in [a, b] {
+            let end = positions.settle_place(end);
+            writeln!(
+                svg,
+                r##"  <line class="pier" x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="#7a5230" stroke-width="{:.2}"/>"##,
+                harbour.x,
+                harbour.y,
+                end.x,
+                end.y,
+                size / 15.,
+            )
+            .unwrap();
+        }
+        circle(&mut svg, "harbour", harbour, size / 4., harbour_color(kind));
+        let ratio = if kind == Harbour::Universal { "3:1" } else { "2:1" };
+        text(&mut svg, harbour, size / 5., "#000", ratio);
+    }
+
+    for (road, owner) in &state.road.owner {
+        let Some(owner) = owner else { continue };
+        let [from, to] = positions.roads[road];
+        writeln!(
+            svg,
+            r##"  <line class="road" x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="{:.2}" stroke-linecap="round"/>"##,
+            from.x,
+            from.y,
+            to.x,
+            to.y,
+            player_color(state.player.profile[*owner].color),
+            size / 8.,
+        )
+        .unwrap();
+    }
+
+    for (settle_place, occupancy) in &state.settle_place.occupancy {
+        let at = positions.settle_place(settle_place);
+        match occupancy {
+            SettlePlace::Empty => {}
+            SettlePlace::Settlement(owner) => {
+                let color = player_color(state.player.profile[*owner].color);
+                circle(&mut svg, "settlement", at, size / 6., color);
+            }
+            SettlePlace::Town(owner) => {
+                let half = size / 5.;
+                writeln!(
+                    svg,
+                    r##"  <rect class="town" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" stroke="#000" stroke-width="{:.2}"/>"##,
+                    at.x - half,
+                    at.y - half,
+                    2. * half,
+                    2. * half,
+                    player_color(state.player.profile[*owner].color),
+                    size / 40.,
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if let Some(robber) = board.robber() {
+        // Next to the marker, so that the number stays readable
+        let center = positions.tile(robber);
+        let at = Point::new(center.x - size / 2., center.y);
+        circle(&mut svg, "robber", at, size / 5., "#333");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn circle(svg: &mut String, class: &str, center: Point, radius: f64, fill: &str) {
+    writeln!(
+        svg,
+        r##"  <circle class="{}" cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{}" stroke="#000" stroke-width="{:.2}"/>"##,
+        class,
+        center.x,
+        center.y,
+        radius,
+        fill,
+        radius / 8.,
+    )
+    .unwrap();
+}
+
+fn text(svg: &mut String, center: Point, font_size: f64, fill: &str, text: impl Display) {
+    writeln!(
+        svg,
+        r#"  <text x="{:.2}" y="{:.2}" font-size="{:.2}" fill="{}" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
+        center.x,
+        center.y,
+        font_size,
+        fill,
+        text,
+    )
+    .unwrap();
+}
+
+fn terrain_color(terrain: TileTerrain) -> &'static str {
+    match terrain {
+        TileTerrain::Field => "#f0c94c",
+        TileTerrain::Pasture => "#9ccf5a",
+        TileTerrain::Forest => "#3f7d3a",
+        TileTerrain::Mesa => "#c8643c",
+        TileTerrain::Mountains => "#8c8c8c",
+        TileTerrain::Desert => "#e8d9a8",
+    }
+}
+
+/// Harbours trading a single resource take the color of the terrain producing it
+fn harbour_color(harbour: Harbour) -> &'static str {
+    match harbour {
+        Harbour::Wheat => terrain_color(TileTerrain::Field),
+        Harbour::Sheep => terrain_color(TileTerrain::Pasture),
+        Harbour::Wood => terrain_color(TileTerrain::Forest),
+        Harbour::Brick => terrain_color(TileTerrain::Mesa),
+        Harbour::Ore => terrain_color(TileTerrain::Mountains),
+        Harbour::Universal => "#fff",
+    }
+}
+
+fn player_color(color: PlayerColor) -> &'static str {
+    match color {
+        PlayerColor::Red => "#d33",
+        PlayerColor::Blue => "#36c",
+        PlayerColor::White => "#eee",
+        PlayerColor::Orange => "#f90",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::{PlayerID, RoadID, SettlePlaceID},
+        layout::Layout,
+        types::SettlePlace,
+        MapConfig,
+    };
+
+    use super::render_svg;
+
+    #[test]
+    fn render_default_map() {
+        let config = MapConfig::standard();
+        let state = decode_config(config, 4).unwrap();
+        let svg = render_svg(&state, &Layout::default());
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polygon").count(), 19);
+        assert!(svg.contains(r##"fill="#e8d9a8""##));
+        // Every tile but the desert has a marker, written instead of the TileID
+        assert_eq!(svg.matches(r#"class="marker""#).count(), 18);
+        assert_eq!(svg.matches(r##"fill="#c00""##).count(), 4);
+        assert!(svg.contains(">12</text>"));
+        assert!(!svg.contains(">0</text>"));
+        assert_eq!(svg.matches(r#"class="robber""#).count(), 1);
+        // Standard map has four universal harbours and five of the resources
+        assert_eq!(svg.matches(r#"class="harbour""#).count(), 9);
+        assert_eq!(svg.matches(r#"class="pier""#).count(), 18);
+        assert_eq!(svg.matches(">3:1</text>").count(), 4);
+        assert_eq!(svg.matches(">2:1</text>").count(), 5);
+        assert!(!svg.contains(r#"class="road""#));
+        assert!(!svg.contains(r#"class="settlement""#));
+    }
+
+    #[test]
+    fn render_pieces() {
+        let mut state = decode_config(MapConfig::standard(), 4).unwrap();
+        let (red, blue) = (PlayerID(0), PlayerID(1));
+        state.road.owner[RoadID(0)] = Some(red);
+        state.road.owner[RoadID(7)] = Some(blue);
+        state.road.owner[RoadID(8)] = Some(blue);
+        state.settle_place.occupancy[SettlePlaceID(0)] = SettlePlace::Settlement(red);
+        state.settle_place.occupancy[SettlePlaceID(9)] = SettlePlace::Town(blue);
+        state.robber = None;
+        let svg = render_svg(&state, &Layout::default());
+
+        assert_eq!(svg.matches(r#"class="road""#).count(), 3);
+        assert_eq!(svg.matches(r##"stroke="#d33""##).count(), 1);
+        assert_eq!(svg.matches(r##"stroke="#36c""##).count(), 2);
+        assert_eq!(svg.matches(r#"class="settlement""#).count(), 1);
+        assert!(svg.contains(r##"<rect class="town""##));
+        assert_eq!(svg.matches(r##"fill="#36c""##).count(), 1);
+        assert!(!svg.contains(r#"class="robber""#));
+    }
+}
@@ -0,0 +1,107 @@
+//! Proptest strategies generating map configs.
+
+use std::collections::HashSet;
+
+use enum_map::Enum;
+use proptest::{arbitrary::Arbitrary, prelude::*, strategy::BoxedStrategy};
+
+use crate::{
+    ids::CoordInt,
+    grid::neighbor_positions,
+    types::{HexSide, TileTerrain},
+    MapConfig, MapConfigBuilder,
+};
+
+/// Size of the map the generated tiles are placed on
+pub const MAP_SIZE: [CoordInt; 2] = [16, 16];
+
+/// Generate placements of up to `max_tiles` tiles, each of which is reachable
+/// from any other one. Islands are grown from the middle of the map by
+/// attaching every next tile to the side of some already placed tile.
+pub fn connected_placement(max_tiles: usize) -> impl Strategy<Value = Vec<[CoordInt; 2]>> {
+    prop::collection::vec((any::<prop::sample::Index>(), 0..HexSide::LENGTH), 0..max_tiles)
+        .prop_map(|steps| {
+            let mut placement = vec![[MAP_SIZE[0] / 2, MAP_SIZE[1] / 2]];
+            let mut placed: HashSet<_> = placement.iter().copied().collect();
+            for (tile, side) in steps {
+                let from = *tile.get(&placement);
+                let Some(position @ [x, y]) = neighbor_positions(from)[HexSide::from_usize(side)]
+                else {
+                    continue;
+                };
+                if x < MAP_SIZE[0] && y < MAP_SIZE[1] && placed.insert(position) {
+                    placement.push(position);
+                }
+            }
+            placement
+        })
+}
+
+/// Generate valid configs of connected maps of up to `max_tiles` tiles,
+/// with arbitrary terrains
+pub fn connected_map(max_tiles: usize) -> impl Strategy<Value = MapConfig> {
+    connected_placement(max_tiles)
+        .prop_flat_map(|placement| {
+            let terrains = prop::collection::vec(
+                prop::sample::select(TileTerrain::ALL.to_vec()),
+                placement.len(),
+            );
+            (Just(placement), terrains)
+        })
+        .prop_map(|(placement, terrains)| {
+            placement
+                .into_iter()
+                .zip(terrains)
+                .fold(
+                    MapConfigBuilder::new().set_map_size(MAP_SIZE),
+                    |builder, (position, terrain)| builder.add_tile(position, terrain),
+                )
+                .build()
+                .expect("generated configs are always valid")
+        })
+}
+
+impl Arbitrary for MapConfig {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Connected maps of up to 64 tiles
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        connected_map(64).boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use crate::{decode_config, topology::verify_topology, MapConfig};
+
+    proptest! {
+        #[test]
+        fn connected_maps_decode(config: MapConfig) {
+            let tile_count = config.tile_placement.len();
+            let state = decode_config(config, 4).unwrap();
+            prop_assert_eq!(state.tile.roads.len(), tile_count);
+            prop_assert_eq!(verify_topology(&state), Ok(()));
+        }
+
+        #[test]
+        fn every_road_connects_two_settle_places(config: MapConfig) {
+            let state = decode_config(config, 4).unwrap();
+            for (road, [a, b]) in &state.road.settle_places {
+                prop_assert_ne!(a, b);
+                prop_assert!(state.settle_place.roads[*a].as_ref().contains(&road));
+                prop_assert!(state.settle_place.roads[*b].as_ref().contains(&road));
+            }
+        }
+
+        #[test]
+        fn every_settle_place_has_two_or_three_roads(config: MapConfig) {
+            let state = decode_config(config, 4).unwrap();
+            for (_, roads) in &state.settle_place.roads {
+                prop_assert!((2..=3).contains(&roads.len()));
+            }
+        }
+    }
+}
@@ -0,0 +1,613 @@
+use std::sync::OnceLock;
+
+use crate::{
+    adjacency_list::AdjacencyList,
+    derive_harbours, derive_resource_tiles, derive_settle_place_roads_relations,
+    ids::{PlayerID, PlayerInt, RoadID, SettlePlaceID, TileID},
+    relations::*,
+    rules::{GameRules, RobberStart},
+    traverse_tiles,
+    types::{PlayerProfile, SettlePlace},
+    DecodeConfigError, MapConfig, ResourceTileDerivation, TileTraversalResult, MAX_PLAYERS,
+    MIN_PLAYERS,
+};
+
+/// Summary of the decoded board shape, attached to the topology errors
+/// in order to make debugging of the traversal on exotic maps easier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("board violates the hex board identity: {violation:?}")]
+pub struct TopologyDiagnostics {
+    pub tiles: usize,
+    pub roads: usize,
+    pub settle_places: usize,
+    /// Amount of groups of tiles which are connected to each other by sides
+    pub components: usize,
+    /// Amount of closed loops formed by the roads which touch only one tile.
+    /// It is the outer coastline of each component plus every lake within them.
+    pub boundary_cycles: usize,
+    pub violation: TopologyViolation,
+}
+
+/// The concrete identity which was found not to hold for the decoded board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TopologyViolation {
+    /// Not every tile from the config got its relations derived by the traversal
+    TileCount { expected: usize, actual: usize },
+    /// Road is connecting settle place to itself
+    DegenerateRoad(RoadID),
+    /// Every road lays on the side of either one or two tiles
+    RoadTileCount { road: RoadID, tiles: usize },
+    /// Every settle place lays on the vertex of one, two or three tiles
+    SettlePlaceTileCount { settle_place: SettlePlaceID, tiles: usize },
+    /// Settle places on the coast (touching single tile) have two roads,
+    /// every other settle place has exactly three roads
+    SettlePlaceDegree {
+        settle_place: SettlePlaceID,
+        expected: usize,
+        actual: usize,
+    },
+    /// Road relations from the both sides (road -> settle place and
+    /// settle place -> road) should mirror each other
+    AsymmetricRelation { road: RoadID, settle_place: SettlePlaceID },
+    /// Tile relations from the both sides (tile -> settle place and
+    /// settle place -> tile) should mirror each other
+    AsymmetricTileRelation { tile: TileID, settle_place: SettlePlaceID },
+    /// V - E + T should be equal to 2C - B, where V, E, T are the amounts of
+    /// settle places, roads and tiles, C is the amount of components, and B is
+    /// the amount of boundary cycles.
+    EulerCharacteristic { expected: i64, actual: i64 },
+    /// Relation does not have an entry for every one of the entities it is
+    /// keyed by, e.g. road owners for every road
+    RelationLength {
+        relation: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// Relation refers to the road which does not exist
+    UnknownRoad(RoadID),
+    /// Relation refers to the settle place which does not exist
+    UnknownSettlePlace(SettlePlaceID),
+    /// Relation refers to the tile which does not exist
+    UnknownTile(TileID),
+}
+
+/// Decoded board of the map config, which games are instantiated from.
+///
+/// Traversing the tiles and verifying the result is the expensive part of
+/// the decoding, and it depends only on the config. Servers hosting many
+/// games on the same map should decode it once, and instantiate every game
+/// from the same topology, which takes only a copy of the relations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardTopology {
+    config: MapConfig,
+    /// State of the board without any players, robber at its default tile
+    board: GameState,
+}
+
+impl BoardTopology {
+    /// Validate the config and decode the board out of it
+    pub fn new(config: MapConfig) -> Result<Self, DecodeConfigError> {
+        use DecodeConfigError::*;
+
+        config.validate().map_err(InvalidConfig)?;
+
+        // Until randomization is implemented, just provide the default distribution of terrains.
+        let dice_marker = AdjacencyList::from_vec(config.dice_markers());
+        let resource = AdjacencyList::from_vec(config.default_tiles.clone());
+        let ResourceTileDerivation {
+            tile_resource_tiles,
+            resource_tiles,
+            dice_markers,
+        } = derive_resource_tiles(&resource, &dice_marker);
+        let TileTraversalResult {
+            tile_settle_places,
+            tile_roads,
+            tile_neighbors,
+            road_settle_places,
+            settle_place_tiles,
+            settle_places_count,
+            tile_grid,
+        } = traverse_tiles(config.map_size, &config.tile_placement);
+
+        let tile_relations = TileEntities {
+            resource,
+            position: AdjacencyList::from_vec(config.tile_placement.clone()),
+            roads: tile_roads,
+            neighbors: tile_neighbors,
+            settle_places: tile_settle_places,
+            dice_marker,
+            resource_tile: tile_resource_tiles,
+            grid: tile_grid,
+        };
+
+        let settle_relations = SettlePlaceEntities {
+            roads: derive_settle_place_roads_relations(&road_settle_places, settle_places_count)?,
+            tiles: settle_place_tiles,
+            occupancy: SettleRelations::from_vec(vec![
+                SettlePlace::Empty;
+                settle_places_count as usize
+            ]),
+        };
+
+        let road_relations = RoadEntities {
+            owner: RoadRelations::from_vec(vec![None; road_settle_places.len()]),
+            settle_places: road_settle_places,
+        };
+
+        let harbour = derive_harbours(&config, &tile_relations)?;
+
+        let board = GameState {
+            player_count: 0,
+            tile: tile_relations,
+            road: road_relations,
+            player: PlayerEntities::default(),
+            settle_place: settle_relations,
+            resource_tile: resource_tiles,
+            dice_marker: dice_markers,
+            harbour,
+            robber: config.robber_tile(),
+        };
+
+        verify_topology(&board).map_err(TopologyInconsistent)?;
+
+        Ok(Self { config, board })
+    }
+
+    /// Topology of the standard map of the base game. It is decoded on the
+    /// first call, and shared by every game in the process afterwards.
+    pub fn standard() -> &'static BoardTopology {
+        static STANDARD: OnceLock<BoardTopology> = OnceLock::new();
+        STANDARD.get_or_init(|| {
+            BoardTopology::new(MapConfig::standard()).expect("standard map decodes")
+        })
+    }
+
+    /// The config the board was decoded from
+    pub fn config(&self) -> &MapConfig {
+        &self.config
+    }
+
+    /// Start a new game on the board. Produces the same state as
+    /// [`crate::decode_config_with_rules`] does for the config.
+    pub fn instantiate(
+        &self,
+        player_count: PlayerInt,
+        rules: &GameRules,
+    ) -> Result<GameState, DecodeConfigError> {
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&player_count) {
+            return Err(DecodeConfigError::InvalidPlayerCount(player_count));
+        }
+
+        let players = player_count as usize;
+        let player = PlayerEntities {
+            placed_roads: PlayerRelations::from_vec(vec![Vec::new(); players]),
+            towns: PlayerRelations::from_vec(vec![Vec::new(); players]),
+            settlements: PlayerRelations::from_vec(vec![Vec::new(); players]),
+            hand: PlayerRelations::from_vec(vec![rules.starting_hand(); players]),
+            profile: PlayerRelations::from_vec(
+                (0..player_count)
+                    .map(|player| PlayerProfile::placeholder(PlayerID(player)))
+                    .collect(),
+            ),
+        };
+        let robber = match rules.robber_start {
+            RobberStart::Desert => self.board.robber,
+            RobberStart::OffBoard => None,
+        };
+
+        Ok(GameState {
+            player_count,
+            player,
+            robber,
+            ..self.board.clone()
+        })
+    }
+}
+
+/// Verify the decoded board against the identities every planar hexagonal
+/// board has to satisfy. Any inconsistency here means that there is a bug
+/// in the traversal, rather than in the map config itself.
+///
+/// The state is not trusted to be well-formed: relations of the wrong
+/// length and references to the entities which do not exist are reported
+/// as violations too.
+///
+/// The board is treated as a planar graph with settle places as vertices
+/// and roads as edges. The faces of such graph are tiles, lakes within the
+/// components and the single outer face. Euler's formula for a graph with
+/// C components (V - E + F = 1 + C) then reduces to V - E + T = 2C - B.
+pub fn verify_topology(state: &GameState) -> Result<(), TopologyDiagnostics> {
+    use TopologyViolation::*;
+
+    let tiles = state.tile.resource.len();
+    let roads = state.road.settle_places.len();
+    let settle_places = state.settle_place.roads.len();
+
+    // References to the unknown entities are skipped while the shape of the
+    // board is summarized, and the first of them is reported afterwards
+    let mut unknown = None;
+
+    let mut road_tiles = vec![Vec::<TileID>::with_capacity(2); roads];
+    for (tile, tile_roads) in &state.tile.roads {
+        for road in tile_roads.values() {
+            match road_tiles.get_mut(usize::from(*road)) {
+                Some(road_tiles) => road_tiles.push(tile),
+                None => _ = unknown.get_or_insert(UnknownRoad(*road)),
+            }
+        }
+    }
+
+    let mut settle_place_tiles = vec![0; settle_places];
+    for (_, tile_settle_places) in &state.tile.settle_places {
+        for settle_place in tile_settle_places.values() {
+            match settle_place_tiles.get_mut(usize::from(*settle_place)) {
+                Some(count) => *count += 1,
+                None => _ = unknown.get_or_insert(UnknownSettlePlace(*settle_place)),
+            }
+        }
+    }
+
+    let known_settle_place =
+        |settle_place: &SettlePlaceID| usize::from(*settle_place) < settle_places;
+    for settle_place in state.road.settle_places.values().flatten() {
+        if !known_settle_place(settle_place) {
+            unknown.get_or_insert(UnknownSettlePlace(*settle_place));
+        }
+    }
+    for road in state.settle_place.roads.values().flatten() {
+        if usize::from(*road) >= roads {
+            unknown.get_or_insert(UnknownRoad(*road));
+        }
+    }
+    let neighbors = state
+        .tile
+        .neighbors
+        .values()
+        .flat_map(|neighbors| neighbors.values().flatten());
+    for tile in state.settle_place.tiles.values().flatten().chain(neighbors) {
+        if usize::from(*tile) >= tiles {
+            unknown.get_or_insert(UnknownTile(*tile));
+        }
+    }
+
+    let shared_sides = road_tiles.iter().filter_map(|tiles| match tiles[..] {
+        [a, b] => Some((usize::from(a), usize::from(b))),
+        _ => None,
+    });
+    let components = count_components(tiles, shared_sides);
+
+    let boundary_roads: Vec<_> = state
+        .road
+        .settle_places
+        .iter()
+        .filter(|(road, [a, b])| {
+            road_tiles[usize::from(*road)].len() == 1
+                && known_settle_place(a)
+                && known_settle_place(b)
+        })
+        .collect();
+    let boundary_cycles = count_components(
+        settle_places,
+        boundary_roads
+            .iter()
+            .map(|(_, [a, b])| (usize::from(*a), usize::from(*b))),
+    ) - (settle_places - boundary_vertex_count(&boundary_roads, settle_places));
+
+    let diagnostics = |violation| TopologyDiagnostics {
+        tiles,
+        roads,
+        settle_places,
+        components,
+        boundary_cycles,
+        violation,
+    };
+
+    if let Some(violation) = unknown {
+        return Err(diagnostics(violation));
+    }
+
+    let tile_relations = [
+        state.tile.roads.len(),
+        state.tile.settle_places.len(),
+        state.tile.neighbors.len(),
+        state.tile.position.len(),
+    ];
+    for actual in tile_relations {
+        if actual != tiles {
+            return Err(diagnostics(TileCount {
+                expected: tiles,
+                actual,
+            }));
+        }
+    }
+
+    let players = state.player_count as usize;
+    let relations = [
+        ("road.owner", roads, state.road.owner.len()),
+        ("settle_place.tiles", settle_places, state.settle_place.tiles.len()),
+        ("settle_place.occupancy", settle_places, state.settle_place.occupancy.len()),
+        ("player.placed_roads", players, state.player.placed_roads.len()),
+        ("player.towns", players, state.player.towns.len()),
+        ("player.settlements", players, state.player.settlements.len()),
+        ("player.hand", players, state.player.hand.len()),
+        ("player.profile", players, state.player.profile.len()),
+    ];
+    for (relation, expected, actual) in relations {
+        if actual != expected {
+            return Err(diagnostics(RelationLength {
+                relation,
+                expected,
+                actual,
+            }));
+        }
+    }
+
+    for (road, [a, b]) in &state.road.settle_places {
+        if a == b {
+            return Err(diagnostics(DegenerateRoad(road)));
+        }
+        for settle_place in [*a, *b] {
+            if !state.settle_place.roads[settle_place].as_ref().contains(&road) {
+                return Err(diagnostics(AsymmetricRelation { road, settle_place }));
+            }
+        }
+        let tiles = road_tiles[usize::from(road)].len();
+        if !(1..=2).contains(&tiles) {
+            return Err(diagnostics(RoadTileCount { road, tiles }));
+        }
+    }
+
+    for (tile, tile_settle_places) in &state.tile.settle_places {
+        for settle_place in tile_settle_places.values() {
+            if !state.settle_place.tiles[*settle_place].as_ref().contains(&tile) {
+                return Err(diagnostics(AsymmetricTileRelation {
+                    tile,
+                    settle_place: *settle_place,
+                }));
+            }
+        }
+    }
+
+    for (settle_place, settle_place_roads) in &state.settle_place.roads {
+        let tiles = settle_place_tiles[usize::from(settle_place)];
+        if !(1..=3).contains(&tiles) {
+            return Err(diagnostics(SettlePlaceTileCount { settle_place, tiles }));
+        }
+        // Every tile of the settle place is known to list it (checked above),
+        // so any extra tiles in the relation show up as the wrong count.
+        let related_tiles = state.settle_place.tiles[settle_place].len();
+        if related_tiles != tiles {
+            return Err(diagnostics(SettlePlaceTileCount {
+                settle_place,
+                tiles: related_tiles,
+            }));
+        }
+        let expected = if tiles == 1 { 2 } else { 3 };
+        let actual = settle_place_roads.as_ref().len();
+        if expected != actual {
+            return Err(diagnostics(SettlePlaceDegree {
+                settle_place,
+                expected,
+                actual,
+            }));
+        }
+        for road in settle_place_roads {
+            if !state.road.settle_places[*road].contains(&settle_place) {
+                return Err(diagnostics(AsymmetricRelation {
+                    road: *road,
+                    settle_place,
+                }));
+            }
+        }
+    }
+
+    let actual = settle_places as i64 - roads as i64 + tiles as i64;
+    let expected = 2 * components as i64 - boundary_cycles as i64;
+    if actual != expected {
+        return Err(diagnostics(EulerCharacteristic { expected, actual }));
+    }
+
+    Ok(())
+}
+
+/// Amount of settle places which are touched by at least one boundary road
+fn boundary_vertex_count(
+    boundary_roads: &[(RoadID, &[SettlePlaceID; 2])],
+    settle_places: usize,
+) -> usize {
+    let mut touched = vec![false; settle_places];
+    for (_, [a, b]) in boundary_roads {
+        touched[usize::from(*a)] = true;
+        touched[usize::from(*b)] = true;
+    }
+    touched.into_iter().filter(|touched| *touched).count()
+}
+
+/// Count connected components of the graph with `nodes` vertices and
+/// specified edges, using union-find.
+fn count_components(nodes: usize, edges: impl Iterator<Item = (usize, usize)>) -> usize {
+    let mut parent: Vec<_> = (0..nodes).collect();
+
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut components = nodes;
+    for (a, b) in edges {
+        let a = find(&mut parent, a);
+        let b = find(&mut parent, b);
+        if a != b {
+            parent[a] = b;
+            components -= 1;
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config, decode_config_with_rules,
+        ids::{RoadID, SettlePlaceID, TileID},
+        rules::{GameRules, RobberStart},
+        types::{HexSide, SettlePlace, TileTerrain},
+        DecodeConfigError, MapConfig, MapConfigBuilder, MAX_PLAYERS, MIN_PLAYERS,
+    };
+
+    use super::{verify_topology, BoardTopology, TopologyViolation};
+
+    #[test]
+    fn default_map_is_consistent() {
+        let state = decode_config(MapConfig::standard(), 4).unwrap();
+        assert_eq!(verify_topology(&state), Ok(()));
+        assert_eq!(state.tile.roads.len(), 19);
+        assert_eq!(state.road.settle_places.len(), 72);
+        assert_eq!(state.settle_place.roads.len(), 54);
+    }
+
+    #[test]
+    fn ring_map_with_lake_is_consistent() {
+        let mut config = MapConfig::standard();
+        // Drop the central desert, leaving a ring with a single lake in the middle
+        config.tile_placement.remove(9);
+        config.default_tiles.remove(9);
+        config.tile_bank.desert = 0;
+        let mut state = decode_config(config, 4).unwrap();
+        assert_eq!(verify_topology(&state), Ok(()));
+
+        // Six lake-side roads are now only bordering a single tile, so the
+        // tampered settle place should be reported with both of the cycles.
+        state.settle_place.roads[SettlePlaceID(0)].push(RoadID(1));
+        let diagnostics = verify_topology(&state).unwrap_err();
+        assert_eq!(diagnostics.boundary_cycles, 2);
+    }
+
+    #[test]
+    fn every_island_is_decoded() {
+        let config = MapConfigBuilder::new()
+            .add_tile([1, 1], TileTerrain::Forest)
+            .add_tile([2, 1], TileTerrain::Field)
+            .add_tile([6, 5], TileTerrain::Mesa)
+            .add_tile([6, 4], TileTerrain::Pasture)
+            .add_tile([9, 9], TileTerrain::Desert)
+            .build()
+            .unwrap();
+        let state = decode_config(config, 2).unwrap();
+        assert_eq!(verify_topology(&state), Ok(()));
+        assert_eq!(state.tile.roads.len(), 5);
+        // Pairs of tiles share a side, and the lonely one has all six to itself
+        assert_eq!(state.road.settle_places.len(), 11 + 11 + 6);
+        assert_eq!(state.settle_place.roads.len(), 10 + 10 + 6);
+        assert_eq!(state.tile.neighbors[TileID(4)].values().flatten().count(), 0);
+    }
+
+    #[test]
+    fn games_are_instantiated_from_the_shared_topology() {
+        let topology = BoardTopology::new(MapConfig::standard()).unwrap();
+        assert_eq!(topology.config(), &MapConfig::standard());
+        let off_board = GameRules {
+            robber_start: RobberStart::OffBoard,
+            ..Default::default()
+        };
+        for player_count in MIN_PLAYERS..=MAX_PLAYERS {
+            for rules in [GameRules::default(), off_board.clone()] {
+                let state = topology.instantiate(player_count, &rules).unwrap();
+                let decoded = decode_config_with_rules(MapConfig::standard(), player_count, &rules);
+                assert_eq!(Ok(state), decoded);
+            }
+        }
+        for player_count in [0, 1, 5] {
+            assert_eq!(
+                topology.instantiate(player_count, &GameRules::default()),
+                Err(DecodeConfigError::InvalidPlayerCount(player_count))
+            );
+        }
+    }
+
+    #[test]
+    fn standard_topology_is_decoded_once() {
+        let standard = BoardTopology::standard();
+        assert!(std::ptr::eq(standard, BoardTopology::standard()));
+        assert_eq!(standard, &BoardTopology::new(MapConfig::standard()).unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| BoardTopology::standard() as *const _ as usize))
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), standard as *const _ as usize);
+        }
+    }
+
+    #[test]
+    fn detects_malformed_relations() {
+        let state = decode_config(MapConfig::standard(), 4).unwrap();
+
+        let mut tampered = state.clone();
+        tampered.road.owner.truncate(0);
+        let diagnostics = verify_topology(&tampered).unwrap_err();
+        assert_eq!(
+            diagnostics.violation,
+            TopologyViolation::RelationLength {
+                relation: "road.owner",
+                expected: 72,
+                actual: 0,
+            }
+        );
+
+        let mut tampered = state.clone();
+        tampered.settle_place.occupancy.push(SettlePlace::Empty);
+        assert!(matches!(
+            verify_topology(&tampered).unwrap_err().violation,
+            TopologyViolation::RelationLength { relation: "settle_place.occupancy", .. }
+        ));
+
+        let mut tampered = state.clone();
+        tampered.player.towns.truncate(0);
+        assert_eq!(
+            verify_topology(&tampered).unwrap_err().violation,
+            TopologyViolation::RelationLength {
+                relation: "player.towns",
+                expected: 4,
+                actual: 0,
+            }
+        );
+
+        let mut tampered = state.clone();
+        tampered.road.settle_places[RoadID(3)][1] = SettlePlaceID(200);
+        let diagnostics = verify_topology(&tampered).unwrap_err();
+        assert_eq!(
+            diagnostics.violation,
+            TopologyViolation::UnknownSettlePlace(SettlePlaceID(200))
+        );
+        assert_eq!(diagnostics.components, 1);
+
+        let mut tampered = state.clone();
+        tampered.tile.roads[TileID(0)][HexSide::East] = RoadID(100);
+        assert_eq!(
+            verify_topology(&tampered).unwrap_err().violation,
+            TopologyViolation::UnknownRoad(RoadID(100))
+        );
+
+        let mut tampered = state;
+        tampered.settle_place.tiles[SettlePlaceID(0)].push(TileID(40));
+        assert_eq!(
+            verify_topology(&tampered).unwrap_err().violation,
+            TopologyViolation::UnknownTile(TileID(40))
+        );
+    }
+
+    #[test]
+    fn detects_mismatched_relations() {
+        let mut state = decode_config(MapConfig::standard(), 4).unwrap();
+        state.road.settle_places[RoadID(0)] = [SettlePlaceID(0), SettlePlaceID(0)];
+        let diagnostics = verify_topology(&state).unwrap_err();
+        assert_eq!(diagnostics.violation, TopologyViolation::DegenerateRoad(RoadID(0)));
+        assert_eq!(diagnostics.tiles, 19);
+        assert_eq!(diagnostics.components, 1);
+        assert_eq!(diagnostics.boundary_cycles, 1);
+    }
+}
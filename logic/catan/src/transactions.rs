@@ -0,0 +1,403 @@
+use enum_map::{enum_map, EnumMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ids::{PlayerID, SettlePlaceID},
+    relations::{GameState, PlayerRelations, SettleRelations},
+    types::{Harbour, PlayerHand, Resource, SettlePlace},
+};
+
+/// Some amount of each of the resources
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResourceBundle(pub EnumMap<Resource, u8>);
+
+/// Resources taken out of the hand
+pub type Cost = ResourceBundle;
+/// Resources put into the hand
+pub type Gain = ResourceBundle;
+
+impl ResourceBundle {
+    pub fn new(resources: EnumMap<Resource, u8>) -> Self {
+        Self(resources)
+    }
+
+    /// Bundle of the given amount of the single resource
+    pub fn single(resource: Resource, amount: u8) -> Self {
+        let mut bundle = Self::default();
+        bundle.0[resource] = amount;
+        bundle
+    }
+
+    /// Total amount of resource cards in the bundle
+    pub fn total(&self) -> u32 {
+        self.0.values().map(|amount| *amount as u32).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(|amount| *amount == 0)
+    }
+
+    /// Cost of a road in the base game
+    pub fn road() -> Cost {
+        Self(enum_map! { Resource::Wood | Resource::Brick => 1, _ => 0 })
+    }
+
+    /// Cost of a settlement in the base game
+    pub fn settlement() -> Cost {
+        Self(enum_map! {
+            Resource::Wood | Resource::Brick | Resource::Wheat | Resource::Sheep => 1,
+            Resource::Ore => 0,
+        })
+    }
+
+    /// Cost of upgrading a settlement to a town in the base game
+    pub fn town() -> Cost {
+        Self(enum_map! {
+            Resource::Wheat => 2,
+            Resource::Ore => 3,
+            _ => 0,
+        })
+    }
+
+    /// Cost of a development card in the base game
+    pub fn development_card() -> Cost {
+        Self(enum_map! { Resource::Wheat | Resource::Sheep | Resource::Ore => 1, _ => 0 })
+    }
+}
+
+/// Reasons the transaction was rejected. Rejected transactions never
+/// change any of the hands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum TransactionError {
+    /// The paying hand does not have enough of the resource
+    #[error("not enough {0:?} to pay")]
+    Insufficient(Resource),
+    /// The receiving hand would hold more of the resource than it can fit
+    #[error("hand can't fit any more {0:?}")]
+    Overflow(Resource),
+    /// Resources are transferred from the player to themselves
+    #[error("player {0:?} can't trade with themselves")]
+    SamePlayer(PlayerID),
+    /// The player is not in the game. Since the IDs may come from the
+    /// network, this is an error rather than a panic.
+    #[error("player {0:?} is not in the game")]
+    UnknownPlayer(PlayerID),
+}
+
+impl PlayerHand {
+    /// Whether the hand holds at least the given resources
+    pub fn can_pay(&self, cost: &Cost) -> bool {
+        self.check_pay(cost).is_ok()
+    }
+
+    /// Take the resources out of the hand, only if it holds all of them
+    pub fn try_pay(&mut self, cost: &Cost) -> Result<(), TransactionError> {
+        self.check_pay(cost)?;
+        for (resource, amount) in cost.0 {
+            self.resources[resource] -= amount;
+        }
+        Ok(())
+    }
+
+    /// Put the resources into the hand, only if all of them fit
+    pub fn credit(&mut self, gain: &Gain) -> Result<(), TransactionError> {
+        self.check_credit(gain)?;
+        for (resource, amount) in gain.0 {
+            self.resources[resource] += amount;
+        }
+        Ok(())
+    }
+
+    fn check_pay(&self, cost: &Cost) -> Result<(), TransactionError> {
+        match cost.0.iter().find(|(resource, amount)| self.resources[*resource] < **amount) {
+            Some((resource, _)) => Err(TransactionError::Insufficient(resource)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_credit(&self, gain: &Gain) -> Result<(), TransactionError> {
+        match gain
+            .0
+            .iter()
+            .find(|(resource, amount)| self.resources[*resource].checked_add(**amount).is_none())
+        {
+            Some((resource, _)) => Err(TransactionError::Overflow(resource)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl GameState {
+    /// Move the resources from one player's hand to another's. Either the
+    /// whole bundle is moved, or neither of the hands is changed.
+    pub fn transfer(
+        &mut self,
+        from: PlayerID,
+        to: PlayerID,
+        bundle: &ResourceBundle,
+    ) -> Result<(), TransactionError> {
+        if from == to {
+            return Err(TransactionError::SamePlayer(from));
+        }
+        self.check_player(from)?;
+        self.check_player(to)?;
+        self.player.hand[from].check_pay(bundle)?;
+        self.player.hand[to].check_credit(bundle)?;
+        self.player.hand[from].try_pay(bundle)?;
+        self.player.hand[to].credit(bundle)
+    }
+
+    /// Swap the resources between two players, as in a trade. Either both
+    /// of the bundles are moved, or neither of the hands is changed.
+    pub fn exchange(
+        &mut self,
+        a: PlayerID,
+        a_gives: &ResourceBundle,
+        b: PlayerID,
+        b_gives: &ResourceBundle,
+    ) -> Result<(), TransactionError> {
+        if a == b {
+            return Err(TransactionError::SamePlayer(a));
+        }
+        self.check_player(a)?;
+        self.check_player(b)?;
+        let (mut hand_a, mut hand_b) = (self.player.hand[a], self.player.hand[b]);
+        hand_a.try_pay(a_gives)?;
+        hand_b.try_pay(b_gives)?;
+        hand_a.credit(b_gives)?;
+        hand_b.credit(a_gives)?;
+        self.player.hand[a] = hand_a;
+        self.player.hand[b] = hand_b;
+        Ok(())
+    }
+
+    fn check_player(&self, player: PlayerID) -> Result<(), TransactionError> {
+        match self.player.hand.contains_key(player) {
+            true => Ok(()),
+            false => Err(TransactionError::UnknownPlayer(player)),
+        }
+    }
+}
+
+/// Amount of the resource traded to the bank for a single resource of any
+/// other kind, by the players without harbours
+pub const BANK_RATIO: u8 = 4;
+/// Ratio of the universal harbour, for any of the resources
+pub const UNIVERSAL_RATIO: u8 = 3;
+/// Ratio of the harbour for the resource it trades
+pub const HARBOUR_RATIO: u8 = 2;
+
+/// Maritime trade ratios of every player, given by the harbours their
+/// settlements are at. Towns are built over the settlements, so they never
+/// change the ratios.
+///
+/// Like the [`crate::network::OccupancyIndex`], the ratios are kept up to
+/// date by telling them about every settlement placed, instead of scanning
+/// the settlements of the player on every trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeRatios {
+    /// Harbour at each of the settle places, if there is any
+    harbours: SettleRelations<Option<Harbour>>,
+    ratios: PlayerRelations<EnumMap<Resource, u8>>,
+}
+
+impl TradeRatios {
+    /// Ratios of the pieces already on the board
+    pub fn new(state: &GameState) -> Self {
+        let mut harbours =
+            SettleRelations::from_vec(vec![None; state.settle_place.occupancy.len()]);
+        for (kind, settle_places) in state.board().harbours() {
+            for settle_place in settle_places {
+                harbours[settle_place] = Some(kind);
+            }
+        }
+        let mut ratios = Self {
+            harbours,
+            ratios: PlayerRelations::from_vec(vec![
+                enum_map! { _ => BANK_RATIO };
+                state.player_count as usize
+            ]),
+        };
+        for (settle_place, occupancy) in &state.settle_place.occupancy {
+            if let SettlePlace::Settlement(owner) | SettlePlace::Town(owner) = occupancy {
+                ratios.settle(settle_place, *owner);
+            }
+        }
+        ratios
+    }
+
+    /// Record the settlement placed by the player
+    pub fn settle(&mut self, settle_place: SettlePlaceID, player: PlayerID) {
+        let Some(harbour) = self.harbours[settle_place] else {
+            return;
+        };
+        let ratios = &mut self.ratios[player];
+        match harbour.resource() {
+            Some(resource) => ratios[resource] = ratios[resource].min(HARBOUR_RATIO),
+            None => {
+                for ratio in ratios.values_mut() {
+                    *ratio = (*ratio).min(UNIVERSAL_RATIO);
+                }
+            }
+        }
+    }
+
+    /// Amount of each of the resources the player trades to the bank for
+    /// a single resource, or None if the player does not play the game
+    pub fn ratios(&self, player: PlayerID) -> Option<&EnumMap<Resource, u8>> {
+        self.ratios.get(player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use enum_map::enum_map;
+
+    use crate::{
+        decode_config,
+        ids::PlayerID,
+        relations::GameState,
+        types::{Harbour, Resource, SettlePlace},
+        MapConfig,
+    };
+
+    use super::{ResourceBundle, TradeRatios, TransactionError};
+
+    fn game() -> GameState {
+        let config = MapConfig::standard();
+        let mut state = decode_config(config, 3).unwrap();
+        state.player.hand[PlayerID(0)].resources =
+            enum_map! { Resource::Wood => 2, Resource::Brick => 1, _ => 0 };
+        state.player.hand[PlayerID(1)].resources = enum_map! { Resource::Ore => 255, _ => 0 };
+        state
+    }
+
+    #[test]
+    fn payments_are_all_or_nothing() {
+        let mut state = game();
+        let hand = &mut state.player.hand[PlayerID(0)];
+
+        assert!(hand.can_pay(&ResourceBundle::road()));
+        assert!(!hand.can_pay(&ResourceBundle::settlement()));
+        assert_eq!(
+            hand.try_pay(&ResourceBundle::settlement()),
+            Err(TransactionError::Insufficient(Resource::Wheat))
+        );
+        assert_eq!(hand.resources[Resource::Wood], 2);
+
+        hand.try_pay(&ResourceBundle::road()).unwrap();
+        assert_eq!(hand.resources[Resource::Wood], 1);
+        assert_eq!(hand.resources[Resource::Brick], 0);
+
+        hand.credit(&ResourceBundle::town()).unwrap();
+        assert_eq!(hand.resources[Resource::Ore], 3);
+    }
+
+    #[test]
+    fn transfers_are_atomic() {
+        let mut state = game();
+        let before = state.clone();
+
+        let ore = ResourceBundle::single(Resource::Ore, 1);
+        assert_eq!(
+            state.transfer(PlayerID(0), PlayerID(1), &ResourceBundle::single(Resource::Wood, 1)),
+            Ok(())
+        );
+        assert_eq!(state.player.hand[PlayerID(1)].resources[Resource::Wood], 1);
+
+        let mut rejected = state.clone();
+        assert_eq!(
+            rejected.transfer(PlayerID(2), PlayerID(1), &ore),
+            Err(TransactionError::Insufficient(Resource::Ore))
+        );
+        // Player 1 can't hold any more ore
+        state.player.hand[PlayerID(2)].resources[Resource::Ore] = 1;
+        let mut rejected = state.clone();
+        assert_eq!(
+            rejected.transfer(PlayerID(2), PlayerID(1), &ore),
+            Err(TransactionError::Overflow(Resource::Ore))
+        );
+        assert_eq!(rejected, state);
+
+        assert_eq!(
+            state.transfer(PlayerID(1), PlayerID(1), &ore),
+            Err(TransactionError::SamePlayer(PlayerID(1)))
+        );
+        assert_ne!(state, before);
+    }
+
+    #[test]
+    fn exchanges_are_atomic() {
+        let mut state = game();
+
+        let wood = ResourceBundle::single(Resource::Wood, 2);
+        let ore = ResourceBundle::single(Resource::Ore, 3);
+        state.exchange(PlayerID(0), &wood, PlayerID(1), &ore).unwrap();
+        assert_eq!(state.player.hand[PlayerID(0)].resources[Resource::Ore], 3);
+        assert_eq!(state.player.hand[PlayerID(1)].resources[Resource::Wood], 2);
+        assert_eq!(state.player.hand[PlayerID(1)].resources[Resource::Ore], 252);
+
+        // Player 0 has no wood left, so neither side of the trade happens
+        let before = state.clone();
+        assert_eq!(
+            state.exchange(PlayerID(0), &wood, PlayerID(1), &ore),
+            Err(TransactionError::Insufficient(Resource::Wood))
+        );
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn unknown_players_are_rejected() {
+        let mut state = game();
+        let before = state.clone();
+        let wood = ResourceBundle::single(Resource::Wood, 1);
+        assert_eq!(
+            state.transfer(PlayerID(0), PlayerID(200), &wood),
+            Err(TransactionError::UnknownPlayer(PlayerID(200)))
+        );
+        assert_eq!(
+            state.exchange(PlayerID(3), &wood, PlayerID(1), &wood),
+            Err(TransactionError::UnknownPlayer(PlayerID(3)))
+        );
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn settlements_at_harbours_lower_the_ratios() {
+        let mut state = game();
+        let mut ratios = TradeRatios::new(&state);
+        let bank = enum_map! { _ => 4 };
+        assert_eq!(ratios.ratios(PlayerID(0)), Some(&bank));
+        assert_eq!(ratios.ratios(PlayerID(3)), None);
+
+        let harbour = |kind| {
+            let mut harbours = state.board().harbours();
+            harbours.find(|(other, _)| *other == kind).map(|(_, settle_places)| settle_places)
+        };
+        let [wood, _] = harbour(Harbour::Wood).unwrap();
+        let [_, universal] = harbour(Harbour::Universal).unwrap();
+
+        ratios.settle(wood, PlayerID(0));
+        state.settle_place.occupancy[wood] = SettlePlace::Settlement(PlayerID(0));
+        let expected = enum_map! { Resource::Wood => 2, _ => 4 };
+        assert_eq!(ratios.ratios(PlayerID(0)), Some(&expected));
+
+        ratios.settle(universal, PlayerID(0));
+        state.settle_place.occupancy[universal] = SettlePlace::Town(PlayerID(0));
+        let expected = enum_map! { Resource::Wood => 2, _ => 3 };
+        assert_eq!(ratios.ratios(PlayerID(0)), Some(&expected));
+        assert_eq!(ratios.ratios(PlayerID(1)), Some(&bank));
+
+        // Harbours are on the coast, so the inland settle places have none
+        let before = ratios.clone();
+        let board = state.board();
+        let inland = board.settle_places().find(|place| board.tiles_of(*place).len() == 3);
+        let inland = inland.unwrap();
+        ratios.settle(inland, PlayerID(1));
+        state.settle_place.occupancy[inland] = SettlePlace::Settlement(PlayerID(1));
+        assert_eq!(ratios, before);
+
+        assert_eq!(TradeRatios::new(&state), ratios);
+    }
+}
@@ -0,0 +1,319 @@
+use enum_map::Enum;
+
+use crate::{
+    coordinates::{directions, Axial, Cube},
+    ids::{CoordInt, TileID, TileInt},
+    types::{HarbourPlacement, HexSide, TileTerrain},
+    MapConfig, TileMap,
+};
+
+/// Symmetry of the hex grid, which keeps the center of the coordinates in
+/// place: some amount of sixths of a turn clockwise, optionally preceded
+/// by mirroring the map from east to west.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Symmetry {
+    mirror: bool,
+    rotation: u8,
+}
+
+impl Symmetry {
+    /// Every one of the twelve symmetries of the hex grid
+    fn all() -> impl Iterator<Item = Symmetry> {
+        [false, true]
+            .into_iter()
+            .flat_map(|mirror| (0..6).map(move |rotation| Symmetry { mirror, rotation }))
+    }
+
+    fn apply(self, position: Axial) -> Axial {
+        let Cube { mut q, mut r, mut s } = position.to_cube();
+        if self.mirror {
+            (q, s) = (s, q);
+        }
+        for _ in 0..self.rotation {
+            (q, r, s) = (-r, -s, -q);
+        }
+        Cube { q, r, s }.to_axial()
+    }
+
+    fn apply_to_side(self, side: HexSide) -> HexSide {
+        let direction = self.apply(directions()[side]);
+        directions()
+            .into_iter()
+            .find(|(_, other)| *other == direction)
+            .map(|(side, _)| side)
+            .expect("symmetries map directions onto directions")
+    }
+}
+
+impl MapConfig {
+    /// The same map, turned clockwise by `steps` sixths of a turn.
+    ///
+    /// Tiles and harbours keep their IDs. The map is moved to the origin,
+    /// and its size is fitted to the tiles and the harbours. Returns None if
+    /// the turned map does not fit the coordinates.
+    pub fn rotated(&self, steps: u8) -> Option<MapConfig> {
+        self.transformed(Symmetry {
+            mirror: false,
+            rotation: steps % 6,
+        })
+    }
+
+    /// The same map, mirrored from east to west.
+    ///
+    /// Tiles and harbours keep their IDs. The map is moved to the origin,
+    /// and its size is fitted to the tiles and the harbours. Returns None if
+    /// the mirrored map does not fit the coordinates.
+    pub fn mirrored(&self) -> Option<MapConfig> {
+        self.transformed(Symmetry {
+            mirror: true,
+            rotation: 0,
+        })
+    }
+
+    /// Canonical form of the map. Configs describing the same board, up to
+    /// rotation, mirroring, translation and the order of the IDs, have the
+    /// same normalized form.
+    ///
+    /// The tiles and the harbours of the normalized map are ordered by their
+    /// positions, and the dice markers and the robber tile are specified
+    /// explicitly wherever they depend on that order. Returns None if the
+    /// config is not valid, or if none of the symmetric maps fit the
+    /// coordinates.
+    pub fn normalized(&self) -> Option<MapConfig> {
+        // Configs deserialized without the validation may be inconsistent,
+        // e.g. have fewer terrains than tiles, which sorting relies upon
+        self.validate().ok()?;
+        Symmetry::all()
+            .filter_map(|symmetry| self.transformed(symmetry))
+            .map(|config| config.sorted_by_position())
+            .map(|config| (config.to_json(), config))
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, config)| config)
+    }
+
+    /// Whether both of the configs describe the same board, up to rotation,
+    /// mirroring, translation and the order of the IDs. Invalid configs are
+    /// not the same board as any other config.
+    pub fn same_board(&self, other: &MapConfig) -> bool {
+        self.normalized().is_some_and(|normalized| Some(normalized) == other.normalized())
+    }
+
+    fn transformed(&self, symmetry: Symmetry) -> Option<MapConfig> {
+        let tiles: Vec<Axial> = self
+            .tile_placement
+            .iter()
+            .map(|position| symmetry.apply(Axial::from_offset(*position)))
+            .collect();
+        let harbours: Vec<Axial> = self
+            .harbour_placement
+            .iter()
+            .map(|harbour| symmetry.apply(Axial::from_offset(harbour.position())))
+            .collect();
+
+        // Move the topmost row to the origin first, since the offset x of the
+        // tile depends on the row it is in
+        let top = tiles.iter().chain(&harbours).map(|position| position.r).min()?;
+        let offset_x = |position: &Axial| {
+            let row = position.r - top;
+            position.q + (row - (row & 1)) / 2
+        };
+        let left = tiles.iter().chain(&harbours).map(offset_x).min()?;
+        let origin = Axial::new(left, top);
+        let to_offset = |position: Axial| (position - origin).to_offset();
+
+        let tile_placement = tiles.into_iter().map(to_offset).collect::<Option<Vec<_>>>()?;
+        let harbour_placement = harbours
+            .into_iter()
+            .zip(&self.harbour_placement)
+            .map(|(position, harbour)| {
+                Some(HarbourPlacement::new(
+                    to_offset(position)?,
+                    symmetry.apply_to_side(harbour.side()),
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let map_size = tile_placement
+            .iter()
+            .chain(harbour_placement.iter().map(|harbour| &harbour.position))
+            .try_fold([0 as CoordInt; 2], |[width, height], [x, y]| {
+                Some([width.max(x.checked_add(1)?), height.max(y.checked_add(1)?)])
+            })?;
+
+        Some(MapConfig {
+            map_size,
+            tile_placement,
+            harbour_placement,
+            ..self.clone()
+        })
+    }
+
+    /// The same map, with the tiles and the harbours ordered by their
+    /// positions, row by row
+    fn sorted_by_position(&self) -> MapConfig {
+        let row_major = |[x, y]: [CoordInt; 2]| (y, x);
+
+        let mut tile_order: Vec<usize> = (0..self.tile_placement.len()).collect();
+        tile_order.sort_by_key(|idx| row_major(self.tile_placement[*idx]));
+        let mut new_ids = vec![TileID(0); tile_order.len()];
+        for (new_idx, old_idx) in tile_order.iter().enumerate() {
+            new_ids[*old_idx] = TileID(new_idx as TileInt);
+        }
+
+        let mut harbour_order: Vec<usize> = (0..self.harbour_placement.len()).collect();
+        harbour_order.sort_by_key(|idx| {
+            let harbour = self.harbour_placement[*idx];
+            (row_major(harbour.position()), harbour.side().into_usize())
+        });
+
+        let dice_markers = self.dice_markers();
+        let mut fixed_tiles = TileMap::<Vec<TileID>>::default();
+        for terrain in TileTerrain::ALL {
+            fixed_tiles[terrain] = self.fixed_tiles[terrain]
+                .iter()
+                .map(|tile| new_ids[usize::from(*tile)])
+                .collect();
+            fixed_tiles[terrain].sort_by_key(|tile| usize::from(*tile));
+        }
+
+        let mut sorted = MapConfig {
+            tile_placement: tile_order.iter().map(|idx| self.tile_placement[*idx]).collect(),
+            default_tiles: tile_order.iter().map(|idx| self.default_tiles[*idx]).collect(),
+            fixed_tiles,
+            harbour_placement: harbour_order
+                .iter()
+                .map(|idx| self.harbour_placement[*idx])
+                .collect(),
+            default_harbours: harbour_order
+                .iter()
+                .map(|idx| self.default_harbours[*idx])
+                .collect(),
+            default_dice_markers: tile_order.iter().map(|idx| dice_markers[*idx]).collect(),
+            robber_tile: None,
+            ..self.clone()
+        };
+        // The robber starts on the first desert unless specified, and which
+        // one is the first depends on the order of the tiles
+        let robber_tile = self.robber_tile().map(|tile| new_ids[usize::from(tile)]);
+        if sorted.robber_tile() != robber_tile {
+            sorted.robber_tile = robber_tile;
+        }
+        sorted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decode_config,
+        ids::TileID,
+        types::{DiceMarker, Harbour, HexSide, TileTerrain},
+        MapConfig, MapConfigBuilder,
+    };
+
+    fn triangle() -> MapConfigBuilder {
+        MapConfigBuilder::new()
+            .add_tile([0, 0], TileTerrain::Forest)
+            .add_tile([1, 0], TileTerrain::Desert)
+            .add_tile([0, 1], TileTerrain::Mesa)
+            .add_harbour([2, 0], HexSide::West, Harbour::Wood)
+            .set_dice_markers(vec![Some(DiceMarker::Six), None, Some(DiceMarker::Two)])
+    }
+
+    #[test]
+    fn transformed_maps_are_valid_boards() {
+        let config = MapConfig::standard();
+        let decoded = decode_config(config.clone(), 2).unwrap();
+        for steps in 0..6 {
+            let rotated = config.rotated(steps).unwrap();
+            assert_eq!(rotated.validate(), Ok(()));
+            let state = decode_config(rotated.clone(), 2).unwrap();
+            assert_eq!(state.road.settle_places.len(), decoded.road.settle_places.len());
+            assert_eq!(state.settle_place.roads.len(), decoded.settle_place.roads.len());
+
+            let mirrored = rotated.mirrored().unwrap();
+            assert_eq!(mirrored.validate(), Ok(()));
+            decode_config(mirrored, 2).unwrap();
+        }
+        let full_turn = (0..6).try_fold(config.clone(), |config, _| config.rotated(1));
+        assert_eq!(full_turn, config.rotated(0));
+        let mirrored_twice = config.mirrored().and_then(|config| config.mirrored());
+        assert_eq!(mirrored_twice, config.rotated(0));
+    }
+
+    #[test]
+    fn rotation_moves_the_neighbors_around() {
+        let config = triangle().build().unwrap();
+        let rotated = config.rotated(1).unwrap();
+        let state = decode_config(rotated.clone(), 2).unwrap();
+        let board = state.board();
+        let side_of = |tile, neighbor| {
+            board.neighbors(tile).find(|(_, other)| *other == neighbor).map(|(side, _)| side)
+        };
+        // The desert was to the east of the forest, and the mesa to the south east
+        assert_eq!(side_of(TileID(0), TileID(1)), Some(HexSide::SouthEast));
+        assert_eq!(side_of(TileID(0), TileID(2)), Some(HexSide::SouthWest));
+        assert_eq!(rotated.harbour_placement()[0].side(), HexSide::NorthWest);
+        let [(_, settle_places)] = board.harbours().collect::<Vec<_>>()[..] else {
+            panic!("the triangle has a single harbour");
+        };
+        assert_eq!(board.resolve_harbour(rotated.harbour_placement()[0]), Ok(settle_places));
+
+        let mirrored = config.mirrored().unwrap();
+        let state = decode_config(mirrored.clone(), 2).unwrap();
+        let board = state.board();
+        let side_of = |tile, neighbor| {
+            board.neighbors(tile).find(|(_, other)| *other == neighbor).map(|(side, _)| side)
+        };
+        assert_eq!(side_of(TileID(0), TileID(1)), Some(HexSide::West));
+        assert_eq!(side_of(TileID(0), TileID(2)), Some(HexSide::SouthWest));
+        assert_eq!(mirrored.harbour_placement()[0].side(), HexSide::East);
+    }
+
+    #[test]
+    fn symmetric_maps_normalize_the_same() {
+        let config = MapConfig::standard();
+        let normalized = config.normalized().unwrap();
+        assert_eq!(normalized.validate(), Ok(()));
+        for steps in 0..6 {
+            let rotated = config.rotated(steps).unwrap();
+            assert_eq!(rotated.normalized().as_ref(), Some(&normalized));
+            assert!(rotated.mirrored().unwrap().same_board(&config));
+        }
+
+        // Adding the tiles in a different order, or further from the origin
+        // still builds the same board, as long as the markers stay on the same tiles
+        let reordered = MapConfigBuilder::new()
+            .add_tile([2, 2], TileTerrain::Mesa)
+            .add_tile([2, 1], TileTerrain::Desert)
+            .add_tile([1, 1], TileTerrain::Forest)
+            .add_harbour([3, 1], HexSide::West, Harbour::Wood)
+            .set_dice_markers(vec![Some(DiceMarker::Two), None, Some(DiceMarker::Six)])
+            .build()
+            .unwrap();
+        assert!(reordered.same_board(&triangle().build().unwrap()));
+
+        let different = triangle()
+            .add_harbour([0, 2], HexSide::NorthEast, Harbour::Universal)
+            .build()
+            .unwrap();
+        assert!(!different.same_board(&triangle().build().unwrap()));
+        let swapped_markers = triangle()
+            .set_dice_markers(vec![Some(DiceMarker::Two), None, Some(DiceMarker::Six)])
+            .build()
+            .unwrap();
+        assert!(!swapped_markers.same_board(&triangle().build().unwrap()));
+    }
+
+    #[test]
+    fn inconsistent_configs_are_not_normalized() {
+        let config = MapConfig::standard();
+        let mut json = serde_json::to_value(&config).unwrap();
+        json["defaultTiles"].as_array_mut().unwrap().pop();
+        let inconsistent: MapConfig = serde_json::from_value(json).unwrap();
+
+        assert_eq!(inconsistent.normalized(), None);
+        assert!(!inconsistent.same_board(&config));
+        assert!(!config.same_board(&inconsistent));
+        assert!(!inconsistent.same_board(&inconsistent));
+    }
+}
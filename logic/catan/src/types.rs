@@ -1,10 +1,14 @@
 use enum_map::{Enum, EnumMap};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::ids::PlayerID;
+use crate::{
+    grid::neighbor_positions,
+    ids::{CoordInt, PlayerID},
+};
 
 /// The five fundamental resources in the game of Catan
-#[derive(Debug, Clone, Copy, Enum, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Enum, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "lowercase")]
 pub enum Resource {
     Wheat,
     Sheep,
@@ -13,9 +17,60 @@ pub enum Resource {
     Ore,
 }
 
+/// Development cards of the base game
+#[derive(Debug, Clone, Copy, Enum, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum DevelopmentCard {
+    Knight,
+    RoadBuilding,
+    YearOfPlenty,
+    Monopoly,
+    VictoryPoint,
+}
+
+/// Colors of the player pieces
+#[derive(Debug, Clone, Copy, Enum, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayerColor {
+    Red,
+    Blue,
+    White,
+    Orange,
+}
+
+impl PlayerColor {
+    pub const ALL: [PlayerColor; 4] =
+        [PlayerColor::Red, PlayerColor::Blue, PlayerColor::White, PlayerColor::Orange];
+}
+
+/// How the player is presented to the other players
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub color: PlayerColor,
+    /// Avatar the player picked. It is up to the clients to interpret it.
+    pub avatar: Option<u32>,
+}
+
+impl PlayerProfile {
+    /// Profile given to the player until the real one is set, e.g. "Player 1"
+    /// of the red color for the PlayerID(0)
+    pub fn placeholder(player: PlayerID) -> Self {
+        let idx = usize::from(player);
+        Self {
+            name: format!("Player {}", idx + 1),
+            color: PlayerColor::ALL[idx % PlayerColor::ALL.len()],
+            avatar: None,
+        }
+    }
+}
+
 /// The six tile terrains in the game of Catan
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/"))]
+#[non_exhaustive]
 pub enum TileTerrain {
     Field,
     Pasture,
@@ -25,7 +80,20 @@ pub enum TileTerrain {
     Desert,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl TileTerrain {
+    /// Every terrain, in the order the fields of the TileMap are declared
+    pub const ALL: [TileTerrain; 6] = [
+        TileTerrain::Field,
+        TileTerrain::Pasture,
+        TileTerrain::Forest,
+        TileTerrain::Mesa,
+        TileTerrain::Mountains,
+        TileTerrain::Desert,
+    ];
+}
+
+/// What is built on the settle place, and by whom
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SettlePlace {
     Settlement(PlayerID),
     Town(PlayerID),
@@ -35,7 +103,10 @@ pub enum SettlePlace {
 /// Markers put on top of the Catan tiles signifying possible
 /// outcomes of a two dice roll (Except for seven, which is 
 /// reserved for robbers actions)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Markers are serialized as the number written on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Serialize, Deserialize, Hash)]
+#[serde(try_from = "u8", into = "u8")]
 pub enum DiceMarker {
     Two,
     Three,
@@ -50,8 +121,79 @@ pub enum DiceMarker {
     Twelve,
 }
 
+impl DiceMarker {
+    /// Every marker, in the ascending order of their numbers
+    pub const ALL: [DiceMarker; 10] = [
+        DiceMarker::Two,
+        DiceMarker::Three,
+        DiceMarker::Four,
+        DiceMarker::Five,
+        DiceMarker::Six,
+        DiceMarker::Eight,
+        DiceMarker::Nine,
+        DiceMarker::Ten,
+        DiceMarker::Eleven,
+        DiceMarker::Twelve,
+    ];
+
+    /// Markers of the base game, in the order they are laid out on the
+    /// resource tiles (the letters A to R on the back of the markers)
+    pub const BASE_GAME: [DiceMarker; 18] = {
+        use DiceMarker::*;
+        [
+            Five, Two, Six, Three, Eight, Ten, Nine, Twelve, Eleven, Four, Eight, Ten, Nine, Four,
+            Five, Six, Three, Eleven,
+        ]
+    };
+
+    /// The number written on the marker
+    pub fn value(self) -> u8 {
+        match self {
+            DiceMarker::Two => 2,
+            DiceMarker::Three => 3,
+            DiceMarker::Four => 4,
+            DiceMarker::Five => 5,
+            DiceMarker::Six => 6,
+            DiceMarker::Eight => 8,
+            DiceMarker::Nine => 9,
+            DiceMarker::Ten => 10,
+            DiceMarker::Eleven => 11,
+            DiceMarker::Twelve => 12,
+        }
+    }
+
+    /// Amount of the outcomes of the two dice roll which add up to the number
+    /// of the marker, out of 36. Conventionally drawn as the dots (pips)
+    /// under the number.
+    pub fn pips(self) -> u8 {
+        6 - 7u8.abs_diff(self.value())
+    }
+}
+
+/// The number is not written on any of the dice markers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("there is no dice marker with the number {0}")]
+pub struct InvalidDiceMarker(pub u8);
+
+impl TryFrom<u8> for DiceMarker {
+    type Error = InvalidDiceMarker;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        DiceMarker::ALL
+            .into_iter()
+            .find(|marker| marker.value() == value)
+            .ok_or(InvalidDiceMarker(value))
+    }
+}
+
+impl From<DiceMarker> for u8 {
+    fn from(marker: DiceMarker) -> Self {
+        marker.value()
+    }
+}
+
 /// Current resources, dev cards and objects left to place of a given player
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct PlayerHand {
     pub resources: EnumMap<Resource, u8>,
     pub settlements: u8,
@@ -60,7 +202,8 @@ pub struct PlayerHand {
 }
 
 /// All of the sides of a hexagonal tile
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, Hash)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/"))]
 pub enum HexSide {
     #[serde(rename = "nw")]
     NorthWest,
@@ -105,7 +248,7 @@ impl HexSide {
 }
 
 /// All of the vertexes of a hexagonal tile
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Hash)]
 pub enum HexVertex {
     North,
     NorthWest,
@@ -115,8 +258,10 @@ pub enum HexVertex {
     South,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/"))]
+#[non_exhaustive]
 pub enum Harbour {
     Wheat,
     Sheep,
@@ -126,8 +271,54 @@ pub enum Harbour {
     Universal,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+impl Harbour {
+    /// The resource the harbour trades at 2:1, or None for the universal
+    /// harbour trading any of them at 3:1
+    pub fn resource(self) -> Option<Resource> {
+        match self {
+            Harbour::Wheat => Some(Resource::Wheat),
+            Harbour::Sheep => Some(Resource::Sheep),
+            Harbour::Wood => Some(Resource::Wood),
+            Harbour::Ore => Some(Resource::Ore),
+            Harbour::Brick => Some(Resource::Brick),
+            Harbour::Universal => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS), ts(export, export_to = "../../../app/src/bindings/"))]
 pub struct HarbourPlacement {
-    position: [u8; 2],
-    side: HexSide,
+    pub(crate) position: [CoordInt; 2],
+    pub(crate) side: HexSide,
+}
+
+impl HarbourPlacement {
+    /// Harbour in the water at the offset coordinates, facing the tile on
+    /// the specified side
+    pub fn new(position: [CoordInt; 2], side: HexSide) -> Self {
+        Self { position, side }
+    }
+
+    /// Offset coordinates of the harbour itself, which are in the water
+    pub fn position(self) -> [CoordInt; 2] {
+        self.position
+    }
+
+    /// Side of the harbour facing the tile it is attached to
+    pub fn side(self) -> HexSide {
+        self.side
+    }
+
+    /// Offset coordinates of the tile the harbour is attached to, or None
+    /// if the side faces beyond the representable coordinates
+    pub fn tile_position(self) -> Option<[CoordInt; 2]> {
+        neighbor_positions(self.position)[self.side]
+    }
+
+    /// Side of the tile the harbour is attached to. The settle places at the
+    /// ends of this side are the ones served by the harbour.
+    pub fn tile_side(self) -> HexSide {
+        self.side.opposite()
+    }
 }